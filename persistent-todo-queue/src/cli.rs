@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+
+use crate::todo::{Recurrence, TaskRef};
+
+#[derive(Debug, Parser)]
+#[command(name = "todo", version, about = "A persistent FIFO todo queue")]
+pub struct Cli {
+    /// Print machine-readable JSON instead of text
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Snapshot file to use; its journal is kept next to it
+    #[arg(long, global = true, value_name = "PATH", default_value = "todo.bin")]
+    pub file: PathBuf,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    #[command(flatten)]
+    Queue(QueueCommand),
+    /// Keep the queue open and serve other `todo` invocations over a Unix socket
+    Serve,
+    /// Browse and edit the queue in an interactive full-screen view
+    Tui,
+}
+
+/// Commands that run against the queue, either directly or through the
+/// daemon. These are the only requests the daemon accepts.
+#[derive(Debug, Subcommand, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueCommand {
+    /// Add a task to the back of the queue
+    Add {
+        /// The task text
+        description: String,
+        /// Re-queue the task this often after it is completed
+        #[arg(long, value_name = "day|week|<N>h")]
+        every: Option<Recurrence>,
+        /// Hold the task back until these task ids are completed
+        #[arg(long, value_name = "ID", value_delimiter = ',')]
+        after: Vec<TaskRef>,
+    },
+    /// List all pending tasks
+    List,
+    /// Preview the next unblocked task without completing it
+    Next,
+    /// Complete the oldest unblocked task
+    Done,
+    /// Change the description of a pending task
+    Edit {
+        /// Id of the task to edit
+        id: TaskRef,
+        /// The new task text
+        description: String,
+    },
+    /// Start tracking time against the next unblocked task
+    Start,
+    /// Stop the running time tracking session
+    Stop,
+    /// Show completion counts, time to completion and tracked time
+    Stats,
+    /// Fold the journal into a new snapshot
+    Compact,
+    /// Merge pending tasks and completions from another todo file
+    Merge {
+        /// Snapshot file from another machine, e.g. a copy of its todo.bin
+        other: PathBuf,
+    },
+}
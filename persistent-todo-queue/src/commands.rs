@@ -0,0 +1,374 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+use persistent_todo_queue::journal::Op;
+use persistent_todo_queue::queue::Queue;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::cli::QueueCommand;
+use crate::error::CliError;
+use crate::todo::{Recurrence, Session, TaskId, TaskRef, Todo};
+use crate::{deps, merge, migrate, stats};
+
+/// Result of a command in both of its printable forms, so the same value can
+/// be produced locally or sent back by the daemon.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Output {
+    pub text: String,
+    pub json: serde_json::Value,
+}
+
+impl Output {
+    fn new(text: impl Into<String>, json: serde_json::Value) -> Self {
+        Output {
+            text: text.into(),
+            json,
+        }
+    }
+}
+
+fn now() -> Result<u64, CliError> {
+    let secs = SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| CliError::Storage(e.into()))?
+        .as_secs();
+    Ok(secs)
+}
+
+/// Id the next locally created task will get.
+fn next_local_id(queue: &Queue<Todo>) -> TaskId {
+    TaskId {
+        origin: queue.origin,
+        counter: queue.next_id,
+    }
+}
+
+/// Validates and builds a new task for `queue`, ready to be enqueued.
+pub fn new_todo(
+    queue: &Queue<Todo>,
+    description: String,
+    every: Option<Recurrence>,
+    after: &[TaskRef],
+) -> Result<Todo, CliError> {
+    let origin = queue.origin;
+    check_description(&description)?;
+    // `--every` already refuses `0h`, but daemon requests skip that parser.
+    if every.is_some_and(|every| every.interval_secs() == 0) {
+        return Err(CliError::BadArgs("a task cannot repeat every 0h".into()));
+    }
+    // Prerequisites must already exist and `after` cannot be changed later,
+    // so a new task can never end up in a dependency cycle.
+    let after: Vec<TaskId> = after.iter().map(|r| r.resolve(origin)).collect();
+    let known = |id: &TaskId| queue.iter().chain(queue.completed()).any(|t| t.id == *id);
+    if let Some(id) = after.iter().find(|id| !known(id)) {
+        return Err(CliError::BadArgs(format!(
+            "no task with id {}",
+            id.label(origin)
+        )));
+    }
+    let created_at = now()?;
+    Ok(Todo {
+        id: next_local_id(queue),
+        created_at,
+        description,
+        due_at: every.map(|_| created_at),
+        recurrence: every,
+        after,
+        completed_at: None,
+        revision: 0,
+        sessions: Vec::new(),
+    })
+}
+
+/// Rejects a blank task description, on add and on edit alike.
+pub fn check_description(description: &str) -> Result<(), CliError> {
+    if description.trim().is_empty() {
+        return Err(CliError::BadArgs("description must not be empty".into()));
+    }
+    Ok(())
+}
+
+/// The task at `index` marked completed, plus its next occurrence if it
+/// recurs. The queue itself is left for the caller to update.
+pub fn complete(queue: &Queue<Todo>, index: usize) -> Result<(Todo, Option<Todo>), CliError> {
+    let mut todo = queue.iter().nth(index).cloned().ok_or(CliError::Empty)?;
+    let completed_at = now()?;
+    todo.stop_tracking(completed_at);
+    todo.completed_at = Some(completed_at);
+    let next = todo.next_instance(next_local_id(queue), completed_at);
+    Ok((todo, next))
+}
+
+/// One line of `list` output: the task with its recurrence, tracking and
+/// blocking notes.
+pub fn list_line(queue: &Queue<Todo>, todo: &Todo) -> String {
+    let origin = queue.origin;
+    let mut notes = Vec::new();
+    if let Some(recurrence) = todo.recurrence {
+        notes.push(recurrence.to_string());
+    }
+    if todo.is_tracking() {
+        notes.push("tracking".to_string());
+    }
+    let blockers = deps::blockers(queue, todo);
+    if !blockers.is_empty() {
+        let ids: Vec<String> = blockers.iter().map(|id| id.label(origin)).collect();
+        notes.push(format!("blocked by {}", ids.join(", ")));
+    }
+    let label = todo.id.label(origin);
+    if notes.is_empty() {
+        format!("[{}] {}", label, todo.description)
+    } else {
+        format!("[{}] {} ({})", label, todo.description, notes.join("; "))
+    }
+}
+
+pub fn execute(queue: &mut Queue<Todo>, command: QueueCommand) -> Result<Output, CliError> {
+    let origin = queue.origin;
+
+    match command {
+        QueueCommand::Add {
+            description,
+            every,
+            after,
+        } => {
+            let todo = new_todo(queue, description, every, &after)?;
+            let added = json!({ "added": todo });
+            queue.record(Op::Enqueue(todo))?;
+            Ok(Output::new("Task added!", added))
+        }
+
+        QueueCommand::Next => {
+            let index = deps::first_ready(queue).ok_or(CliError::Empty)?;
+            let todo = queue.iter().nth(index).ok_or(CliError::Empty)?;
+            Ok(Output::new(
+                format!("Next up: [{}] {}", todo.id.label(origin), todo.description),
+                json!({ "next": todo }),
+            ))
+        }
+
+        QueueCommand::Done => {
+            let index = deps::first_ready(queue).ok_or(CliError::Empty)?;
+            let (todo, next) = complete(queue, index)?;
+            let mut lines = vec![format!("About to complete: {}", todo.description)];
+            queue.record(match next.clone() {
+                Some(next) => Op::CompleteAndEnqueue {
+                    index: index as u64,
+                    item: todo.clone(),
+                    next,
+                },
+                None => Op::Complete {
+                    index: index as u64,
+                    item: todo.clone(),
+                },
+            })?;
+            lines.push(format!(
+                "Completed: [{}] {}",
+                todo.id.label(origin),
+                todo.description
+            ));
+            if let Some(next) = &next {
+                lines.push(format!(
+                    "Next occurrence queued: [{}] {}",
+                    next.id.label(origin),
+                    next.description
+                ));
+            }
+            Ok(Output::new(
+                lines.join("\n"),
+                json!({ "completed": todo, "recurred": next }),
+            ))
+        }
+
+        QueueCommand::Edit { id, description } => {
+            check_description(&description)?;
+            let id = id.resolve(origin);
+            let (index, mut todo) = queue
+                .iter()
+                .enumerate()
+                .find(|(_, todo)| todo.id == id)
+                .map(|(index, todo)| (index, todo.clone()))
+                .ok_or_else(|| {
+                    CliError::BadArgs(format!("no task with id {}", id.label(origin)))
+                })?;
+            todo.description = description;
+            todo.revision += 1;
+            let edited = json!({ "edited": todo });
+            queue.record(Op::Edit {
+                index: index as u64,
+                item: todo,
+            })?;
+            Ok(Output::new(
+                format!("Task {} updated!", id.label(origin)),
+                edited,
+            ))
+        }
+
+        QueueCommand::Start => {
+            if let Some(running) = queue.iter().find(|todo| todo.is_tracking()) {
+                return Err(CliError::BadArgs(format!(
+                    "already tracking [{}]; run `todo stop` first",
+                    running.id.label(origin)
+                )));
+            }
+            let index = deps::first_ready(queue).ok_or(CliError::Empty)?;
+            let mut todo = queue.iter().nth(index).cloned().ok_or(CliError::Empty)?;
+            todo.sessions.push(Session {
+                started_at: now()?,
+                stopped_at: None,
+            });
+            todo.revision += 1;
+            let started = json!({ "started": todo });
+            let text = format!("Tracking: [{}] {}", todo.id.label(origin), todo.description);
+            queue.record(Op::Edit {
+                index: index as u64,
+                item: todo,
+            })?;
+            Ok(Output::new(text, started))
+        }
+
+        QueueCommand::Stop => {
+            let (index, mut todo) = queue
+                .iter()
+                .enumerate()
+                .find(|(_, todo)| todo.is_tracking())
+                .map(|(index, todo)| (index, todo.clone()))
+                .ok_or_else(|| CliError::BadArgs("no task is being tracked".into()))?;
+            todo.stop_tracking(now()?);
+            todo.revision += 1;
+            let session = todo.sessions.last().copied();
+            let elapsed = session
+                .and_then(|s| Some(s.stopped_at?.saturating_sub(s.started_at)))
+                .unwrap_or(0);
+            let text = format!(
+                "Stopped: [{}] {} after {}",
+                todo.id.label(origin),
+                todo.description,
+                stats::format_duration(elapsed)
+            );
+            let stopped = json!({ "stopped": todo, "session": session });
+            queue.record(Op::Edit {
+                index: index as u64,
+                item: todo,
+            })?;
+            Ok(Output::new(text, stopped))
+        }
+
+        QueueCommand::Stats => {
+            let stats = stats::compute(queue, now()?);
+            let mut lines = vec!["Completed per day:".to_string()];
+            if stats.completed_per_day.is_empty() {
+                lines.push("  none yet".to_string());
+            }
+            for (day, count) in &stats.completed_per_day {
+                lines.push(format!("  {day}  {count}"));
+            }
+            lines.push(match stats.average_completion_secs {
+                Some(secs) => format!(
+                    "Average time to completion: {}",
+                    stats::format_duration(secs)
+                ),
+                None => "Average time to completion: n/a".to_string(),
+            });
+            lines.push("Tracked time:".to_string());
+            if stats.tracked.is_empty() {
+                lines.push("  none yet".to_string());
+            }
+            for tracked in &stats.tracked {
+                lines.push(format!(
+                    "  [{}] {}  {}",
+                    tracked.id.label(origin),
+                    tracked.description,
+                    stats::format_duration(tracked.secs)
+                ));
+            }
+            Ok(Output::new(lines.join("\n"), json!({ "stats": stats })))
+        }
+
+        QueueCommand::Compact => {
+            queue.save()?;
+            Ok(Output::new(
+                "Journal compacted into snapshot.",
+                json!({ "compacted": true }),
+            ))
+        }
+
+        QueueCommand::Merge { other } => {
+            let path = queue
+                .path()
+                .map(Path::to_path_buf)
+                .ok_or_else(|| CliError::Storage("queue has no backing file".into()))?;
+            let theirs = migrate::load(&other)?;
+            let (mut merged, report) = merge::merge(queue, &theirs);
+            merged.save_to(path)?;
+            *queue = merged;
+            let mut lines = vec![format!(
+                "Merged {}: {} task(s) added, {} completion(s) applied, {} conflict(s)",
+                other.display(),
+                report.added,
+                report.completed,
+                report.conflicts.len()
+            )];
+            for conflict in &report.conflicts {
+                lines.push(format!(
+                    "  [{}] {}",
+                    conflict.id.label(origin),
+                    conflict.reason
+                ));
+            }
+            Ok(Output::new(lines.join("\n"), json!({ "merged": report })))
+        }
+
+        QueueCommand::List => {
+            let todos: Vec<&Todo> = queue.iter().collect();
+            let blocked: Vec<TaskId> = queue
+                .iter()
+                .filter(|todo| !deps::blockers(queue, todo).is_empty())
+                .map(|todo| todo.id)
+                .collect();
+            let value = json!({ "pending": todos, "blocked": blocked });
+            if queue.is_empty() {
+                return Ok(Output::new("No tasks!", value));
+            }
+            let mut lines = vec![format!("{} task(s) pending:", queue.len())];
+            lines.extend(queue.iter().map(|todo| list_line(queue, todo)));
+            Ok(Output::new(lines.join("\n"), value))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(queue: &Queue<Todo>, description: &str) -> Todo {
+        new_todo(queue, description.to_string(), None, &[]).unwrap()
+    }
+
+    fn bad_args(result: Result<Output, CliError>) -> String {
+        match result {
+            Err(CliError::BadArgs(message)) => message,
+            other => panic!("expected bad args, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_edit_rejects_empty_description() {
+        let mut queue = Queue::new();
+        let task = todo(&queue, "buy milk");
+        queue.enqueue(task);
+
+        for description in ["", "   "] {
+            let edit = QueueCommand::Edit {
+                id: "1".parse().unwrap(),
+                description: description.to_string(),
+            };
+            assert_eq!(
+                bad_args(execute(&mut queue, edit)),
+                "description must not be empty"
+            );
+        }
+        assert_eq!(queue.iter().next().unwrap().description, "buy milk");
+    }
+}
@@ -0,0 +1,189 @@
+use std::error::Error;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use persistent_todo_queue::queue::Queue;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::QueueCommand;
+use crate::commands::{self, Output};
+use crate::error::CliError;
+use crate::migrate;
+use crate::todo::Todo;
+
+/// How long either side waits on the other before giving up on a request.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One line of JSON sent back for every request line.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Response {
+    Ok(Output),
+    Err(WireError),
+}
+
+/// A `CliError` flattened to its kind and message so it keeps its exit code
+/// on the client side.
+#[derive(Debug, Serialize, Deserialize)]
+struct WireError {
+    kind: String,
+    message: String,
+}
+
+impl From<&CliError> for WireError {
+    fn from(e: &CliError) -> Self {
+        let message = match e {
+            CliError::Storage(inner) => inner.to_string(),
+            e => e.to_string(),
+        };
+        WireError {
+            kind: e.kind().to_string(),
+            message,
+        }
+    }
+}
+
+impl From<WireError> for CliError {
+    fn from(e: WireError) -> Self {
+        match e.kind.as_str() {
+            "empty" => CliError::Empty,
+            "bad_args" => CliError::BadArgs(e.message),
+            _ => CliError::Storage(e.message.into()),
+        }
+    }
+}
+
+/// The daemon for `file` listens next to it, e.g. `todo.bin` -> `todo.sock`.
+/// `main` makes `file` absolute, so this does not depend on the current
+/// directory.
+fn socket_path(file: &Path) -> PathBuf {
+    file.with_extension("sock")
+}
+
+/// Whether a daemon is currently listening for `file`.
+pub fn is_running(file: &Path) -> bool {
+    UnixStream::connect(socket_path(file)).is_ok()
+}
+
+/// Keeps the queue at `file` in memory and answers commands sent by
+/// [`request`] until the process is killed.
+///
+/// Requests are handled one at a time, so every command sees the effects of
+/// the ones before it without any locking.
+pub fn serve(file: &Path) -> Result<(), CliError> {
+    let socket = socket_path(file);
+    if socket.exists() {
+        if is_running(file) {
+            return Err(CliError::BadArgs(format!(
+                "a daemon is already serving {}",
+                file.display()
+            )));
+        }
+        // Left behind by a daemon that did not shut down cleanly.
+        fs::remove_file(&socket).map_err(|e| CliError::Storage(e.into()))?;
+    }
+
+    let mut queue = migrate::open(file)?;
+    let listener = UnixListener::bind(&socket).map_err(|e| CliError::Storage(e.into()))?;
+    eprintln!("Serving {} on {}", file.display(), socket.display());
+
+    for stream in listener.incoming() {
+        let result = stream
+            .map_err(Into::into)
+            .and_then(|stream| handle(&mut queue, stream));
+        if let Err(e) = result {
+            eprintln!("Error: {e}");
+        }
+    }
+    Ok(())
+}
+
+fn handle(queue: &mut Queue<Todo>, mut stream: UnixStream) -> Result<(), Box<dyn Error>> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut line = String::new();
+    if BufReader::new(&stream).read_line(&mut line)? == 0 {
+        // A liveness probe from `is_running`, not a request.
+        return Ok(());
+    }
+
+    // `serve` and `tui` are not queue commands, so they fail to parse here
+    // like any other unknown request.
+    let response = match serde_json::from_str::<QueueCommand>(&line) {
+        Ok(command) => match commands::execute(queue, command) {
+            Ok(output) => Response::Ok(output),
+            Err(e) => Response::Err(WireError::from(&e)),
+        },
+        Err(e) => Response::Err(WireError {
+            kind: "bad_args".to_string(),
+            message: format!("invalid request: {e}"),
+        }),
+    };
+    writeln!(stream, "{}", serde_json::to_string(&response)?)?;
+    Ok(())
+}
+
+/// Sends `command` to the daemon serving `file`. Returns `None` when no daemon
+/// is listening, in which case the caller should use the file directly.
+pub fn request(file: &Path, command: &QueueCommand) -> Option<Result<Output, CliError>> {
+    let stream = UnixStream::connect(socket_path(file)).ok()?;
+    Some(match exchange(stream, command) {
+        Ok(Response::Ok(output)) => Ok(output),
+        Ok(Response::Err(e)) => Err(e.into()),
+        Err(e) => Err(CliError::Storage(e)),
+    })
+}
+
+fn exchange(mut stream: UnixStream, command: &QueueCommand) -> Result<Response, Box<dyn Error>> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    writeln!(stream, "{}", serde_json::to_string(command)?)?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn send(queue: &mut Queue<Todo>, line: &str) -> Response {
+        let (mut client, server) = UnixStream::pair().unwrap();
+        writeln!(client, "{line}").unwrap();
+        handle(queue, server).unwrap();
+        let mut response = String::new();
+        BufReader::new(&client).read_line(&mut response).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    fn error_kind(response: Response) -> String {
+        match response {
+            Response::Err(e) => e.kind,
+            Response::Ok(output) => panic!("unexpected output {output:?}"),
+        }
+    }
+
+    #[test]
+    fn test_rejects_non_queue_commands() {
+        let mut queue = Queue::new();
+        for line in [r#""serve""#, r#""tui""#, r#"{"frobnicate":{}}"#, "not json"] {
+            assert_eq!(error_kind(send(&mut queue, line)), "bad_args", "{line}");
+        }
+
+        // Queue commands are still executed and answered.
+        assert_eq!(error_kind(send(&mut queue, r#""next""#)), "empty");
+        match send(&mut queue, r#""list""#) {
+            Response::Ok(output) => assert_eq!(output.text, "No tasks!"),
+            Response::Err(e) => panic!("unexpected error {}", e.message),
+        }
+    }
+
+    #[test]
+    fn test_rejects_zero_interval() {
+        let mut queue = Queue::new();
+        let line = r#"{"add":{"description":"x","every":{"hours":0},"after":[]}}"#;
+        assert_eq!(error_kind(send(&mut queue, line)), "bad_args");
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::{HashMap, HashSet};
+
+use persistent_todo_queue::queue::Queue;
+
+use crate::todo::{TaskId, Todo};
+
+/// Ids in `todo.after` that are still pending. A prerequisite counts as completed
+/// once it is no longer in the queue.
+pub fn blockers(queue: &Queue<Todo>, todo: &Todo) -> Vec<TaskId> {
+    let pending: HashSet<TaskId> = queue.iter().map(|t| t.id).collect();
+    todo.after
+        .iter()
+        .copied()
+        .filter(|id| pending.contains(id))
+        .collect()
+}
+
+/// FIFO index of the oldest task whose prerequisites are all completed.
+///
+/// Blocked tasks are skipped over in place rather than moved, so once they
+/// unblock they are served in their original position.
+pub fn first_ready(queue: &Queue<Todo>) -> Option<usize> {
+    let pending: HashSet<TaskId> = queue.iter().map(|t| t.id).collect();
+    queue
+        .iter()
+        .position(|todo| todo.after.iter().all(|id| !pending.contains(id)))
+}
+
+/// Pending tasks among `todos` that wait on themselves through their `after`
+/// lists. `add` cannot build such a cycle, since prerequisites must already
+/// exist and `after` never changes, but a merged file can bring one in.
+pub fn cycles<'a>(todos: impl IntoIterator<Item = &'a Todo>) -> HashSet<TaskId> {
+    let after: HashMap<TaskId, &[TaskId]> = todos
+        .into_iter()
+        .map(|todo| (todo.id, todo.after.as_slice()))
+        .collect();
+    let reaches_itself = |start: TaskId| {
+        let mut seen = HashSet::new();
+        let mut stack = after[&start].to_vec();
+        while let Some(id) = stack.pop() {
+            if id == start {
+                return true;
+            }
+            if seen.insert(id) {
+                stack.extend_from_slice(after.get(&id).copied().unwrap_or_default());
+            }
+        }
+        false
+    };
+    after
+        .keys()
+        .copied()
+        .filter(|&id| reaches_itself(id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use persistent_todo_queue::journal::Op;
+
+    use super::*;
+    use crate::commands;
+    use crate::merge;
+    use crate::todo::FULL_HISTORY;
+
+    fn todo(queue: &Queue<Todo>, counter: u64, after: &[u64]) -> Todo {
+        let id = |counter| TaskId {
+            origin: queue.origin,
+            counter,
+        };
+        Todo {
+            id: id(counter),
+            description: format!("task {counter}"),
+            created_at: 1_700_000_000 + counter,
+            due_at: None,
+            recurrence: None,
+            after: after.iter().copied().map(id).collect(),
+            completed_at: None,
+            revision: 0,
+            sessions: Vec::new(),
+        }
+    }
+
+    fn counters(queue: &Queue<Todo>) -> Vec<u64> {
+        queue.iter().map(|todo| todo.id.counter).collect()
+    }
+
+    #[test]
+    fn test_blocked_tasks_keep_their_place() {
+        let mut queue = Queue::new();
+        for (counter, after) in [(1, &[][..]), (2, &[3]), (3, &[])] {
+            let todo = todo(&queue, counter, after);
+            queue.enqueue(todo);
+        }
+
+        assert_eq!(first_ready(&queue), Some(0));
+        queue.remove(0);
+
+        // Task 2 waits for task 3, which is served first without moving 2.
+        let blocked = queue.iter().next().unwrap();
+        assert_eq!(blockers(&queue, blocked), vec![blocked.after[0]]);
+        assert_eq!(
+            commands::list_line(&queue, blocked),
+            "[2] task 2 (blocked by 3)"
+        );
+        assert_eq!(first_ready(&queue), Some(1));
+        assert_eq!(counters(&queue), vec![2, 3]);
+
+        // Completing the prerequisite unblocks it.
+        queue.remove(1);
+        let unblocked = queue.iter().next().unwrap();
+        assert!(blockers(&queue, unblocked).is_empty());
+        assert_eq!(commands::list_line(&queue, unblocked), "[2] task 2");
+        assert_eq!(first_ready(&queue), Some(0));
+    }
+
+    #[test]
+    fn test_everything_blocked() {
+        let mut queue = Queue::new();
+        let todo = todo(&queue, 1, &[1]);
+        queue.enqueue(todo);
+        assert_eq!(first_ready(&queue), None);
+    }
+
+    #[test]
+    fn test_add_cannot_close_a_cycle() {
+        let mut queue = Queue::new();
+        let first = commands::new_todo(&queue, "first".into(), None, &[]).unwrap();
+        queue.apply(Op::Enqueue(first));
+
+        // Only ids that exist can be named, so not the task being added, nor
+        // any later one that could come to depend on it.
+        for after in ["2", "3"] {
+            let after = [after.parse().unwrap()];
+            assert!(commands::new_todo(&queue, "second".into(), None, &after).is_err());
+        }
+        let after = ["1".parse().unwrap()];
+        let second = commands::new_todo(&queue, "second".into(), None, &after).unwrap();
+        queue.enqueue(second);
+        assert!(cycles(queue.iter()).is_empty());
+    }
+
+    #[test]
+    fn test_after_long_completed_task() {
+        let mut queue = Queue::new();
+        for counter in 1..FULL_HISTORY as u64 + 10 {
+            let mut todo = todo(&queue, counter, &[]);
+            todo.completed_at = Some(todo.created_at);
+            queue.push_completed(todo);
+        }
+        queue.next_id = FULL_HISTORY as u64 + 10;
+        queue.compact_completed(FULL_HISTORY, Todo::archive);
+
+        let after = ["1".parse().unwrap()];
+        let todo = commands::new_todo(&queue, "later".into(), None, &after).unwrap();
+        queue.enqueue(todo);
+        let todo = queue.iter().next().unwrap();
+        assert!(blockers(&queue, todo).is_empty());
+    }
+
+    #[test]
+    fn test_merge_clears_cycles() {
+        let mut ours = Queue::new();
+        let theirs_origin = ours.origin.wrapping_add(1);
+        let mut theirs = Queue::new();
+        theirs.origin = theirs_origin;
+
+        // A hand-edited file where 1 and 2 wait on each other and 3 waits on them.
+        let mut cycle = vec![
+            todo(&ours, 1, &[2]),
+            todo(&ours, 2, &[1]),
+            todo(&ours, 3, &[1]),
+        ];
+        let free = cycle.pop().unwrap();
+        ours.enqueue(todo(&ours, 4, &[]));
+        theirs.extend(cycle);
+        theirs.enqueue(free);
+        assert_eq!(cycles(theirs.iter()).len(), 2);
+
+        let (merged, report) = merge::merge(&ours, &theirs);
+        assert!(cycles(merged.iter()).is_empty());
+        let after: Vec<usize> = merged.iter().map(|todo| todo.after.len()).collect();
+        assert_eq!(after, vec![0, 0, 1, 0]);
+        let reported: Vec<u64> = report.conflicts.iter().map(|c| c.id.counter).collect();
+        assert_eq!(reported, vec![1, 2]);
+    }
+}
@@ -0,0 +1,47 @@
+use std::fmt;
+
+// Same code clap exits with when argument parsing fails.
+pub const EXIT_BAD_ARGS: i32 = 2;
+pub const EXIT_EMPTY: i32 = 3;
+pub const EXIT_STORAGE: i32 = 4;
+
+#[derive(Debug)]
+pub enum CliError {
+    Empty,
+    BadArgs(String),
+    Storage(Box<dyn std::error::Error>),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Empty => EXIT_EMPTY,
+            CliError::BadArgs(_) => EXIT_BAD_ARGS,
+            CliError::Storage(_) => EXIT_STORAGE,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CliError::Empty => "empty",
+            CliError::BadArgs(_) => "bad_args",
+            CliError::Storage(_) => "storage",
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Empty => write!(f, "no tasks"),
+            CliError::BadArgs(msg) => write!(f, "{msg}"),
+            CliError::Storage(e) => write!(f, "storage error: {e}"),
+        }
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for CliError {
+    fn from(e: Box<dyn std::error::Error>) -> Self {
+        CliError::Storage(e)
+    }
+}
@@ -0,0 +1,34 @@
+//! Header at the start of every snapshot and journal file: four magic bytes
+//! naming the kind of file, then the format version.
+
+use std::error::Error;
+
+/// Version of the snapshot and journal encoding. Bump it whenever `Queue`,
+/// `Record` or `Op` change shape.
+pub const VERSION: u8 = 1;
+
+pub const SNAPSHOT_MAGIC: [u8; 4] = *b"TDQS";
+pub const JOURNAL_MAGIC: [u8; 4] = *b"TDQJ";
+
+pub const HEADER_LEN: usize = 5;
+
+pub fn header(magic: [u8; 4]) -> [u8; HEADER_LEN] {
+    let [a, b, c, d] = magic;
+    [a, b, c, d, VERSION]
+}
+
+/// Returns what follows the header, or `None` if `bytes` does not start with
+/// `magic`. Fails if the file was written in another format version.
+pub fn strip(bytes: &[u8], magic: [u8; 4]) -> Result<Option<&[u8]>, Box<dyn Error>> {
+    let Some(rest) = bytes.strip_prefix(&magic) else {
+        return Ok(None);
+    };
+    match rest.split_first() {
+        Some((&VERSION, body)) => Ok(Some(body)),
+        Some((version, _)) => Err(format!(
+            "file format version {version} is not supported (expected {VERSION})"
+        )
+        .into()),
+        None => Err("file header is truncated".into()),
+    }
+}
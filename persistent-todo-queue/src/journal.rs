@@ -0,0 +1,123 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::format::{self, HEADER_LEN, JOURNAL_MAGIC};
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub enum Op<T> {
+    Enqueue(T),
+    Dequeue,
+    Edit {
+        index: u64,
+        item: T,
+    },
+    Remove {
+        index: u64,
+    },
+    /// Moves the item at `index` to the completed history, replaced by `item`.
+    Complete {
+        index: u64,
+        item: T,
+    },
+    /// Moves the item at `from` to position `to`, shifting the items between.
+    Move {
+        from: u64,
+        to: u64,
+    },
+    /// `Complete` followed by `Enqueue(next)`, journaled as one record so a
+    /// crash cannot keep the completion and lose the follow-up.
+    CompleteAndEnqueue {
+        index: u64,
+        item: T,
+        next: T,
+    },
+}
+
+/// An op as stored in the journal. `seq` increases by one with every record
+/// and is never reset, so a snapshot can tell which records it already holds.
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct Record<T> {
+    pub seq: u64,
+    pub op: Op<T>,
+}
+
+// The file starts with the format header. Each record is a little-endian u32
+// length followed by the borsh-encoded record. The length prefix lets `read`
+// detect a record torn by a crash mid-append.
+pub fn append<T: BorshSerialize>(
+    path: impl AsRef<Path>,
+    record: &Record<T>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = borsh::to_vec(record)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    let mut buf = Vec::with_capacity(HEADER_LEN + 4 + bytes.len());
+    if file.metadata()?.len() == 0 {
+        buf.extend_from_slice(&format::header(JOURNAL_MAGIC));
+    }
+    buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&bytes);
+    file.write_all(&buf)?;
+    file.sync_data()?;
+    Ok(())
+}
+
+/// Reads the journal up to its last valid record.
+///
+/// Reading stops at the first record that is torn, does not decode or does not
+/// follow the previous one in sequence; everything before it is intact. The
+/// file is truncated there so later appends are not stranded behind it.
+pub fn read<T: BorshDeserialize>(
+    path: impl AsRef<Path>,
+) -> Result<Vec<Record<T>>, Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let (body, start) = match format::strip(&bytes, JOURNAL_MAGIC)? {
+        Some(body) => (body, HEADER_LEN),
+        // Only the start of the header made it to disk before a crash.
+        None if bytes.len() < HEADER_LEN && JOURNAL_MAGIC.starts_with(&bytes) => (&[][..], 0),
+        None => return Err("journal has no format header".into()),
+    };
+
+    let mut records: Vec<Record<T>> = Vec::new();
+    let mut offset = 0;
+    while let Some((record, len)) = decode::<T>(&body[offset..]) {
+        if records.last().is_some_and(|last| record.seq <= last.seq) {
+            break;
+        }
+        records.push(record);
+        offset += len;
+    }
+
+    if start + offset < bytes.len() {
+        OpenOptions::new()
+            .write(true)
+            .open(path)?
+            .set_len((start + offset) as u64)?;
+    }
+    Ok(records)
+}
+
+/// Decodes the record at the start of `bytes`, returning it with its length
+/// including the prefix.
+fn decode<T: BorshDeserialize>(bytes: &[u8]) -> Option<(Record<T>, usize)> {
+    let len = u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?) as usize;
+    let record = borsh::from_slice(bytes.get(4..4 + len)?).ok()?;
+    Some((record, 4 + len))
+}
+
+pub fn clear(path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
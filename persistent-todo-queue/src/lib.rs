@@ -0,0 +1,3 @@
+mod format;
+pub mod journal;
+pub mod queue;
@@ -1,63 +1,101 @@
-mod queue;
+mod cli;
+mod commands;
+mod daemon;
+mod deps;
+mod error;
+mod merge;
+mod migrate;
+mod stats;
 mod todo;
+mod tui;
 
-use std::time::SystemTime;
-
-use queue::Queue;
-use todo::Todo;
-
-fn main() {
-    let mut queue: Queue<Todo> = Queue::load().unwrap_or_else(|_| Queue::new());
-
-    let args: Vec<String> = std::env::args().collect();
-
-    match args.get(1).map(|s| s.as_str()) {
-        Some("add") => {
-            let todo = Todo {
-                id: queue.next_id,
-                created_at: SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-                description: args.get(2).expect("provide a description").clone(),
-            };
-            queue.next_id += 1;
-            queue.enqueue(todo);
-            queue.save().unwrap();
-            println!("Task added!");
-        }
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-        Some("next") => match queue.peek() {
-            Some(todo) => println!("Next up: [{}] {}", todo.id, todo.description),
-            None => println!("No tasks!"),
-        },
+use clap::Parser;
+use serde_json::json;
 
-        Some("done") => {
-            if queue.is_empty() {
-                println!("No tasks to complete!");
-                return;
-            }
-            if let Some(todo) = queue.peek() {
-                println!("About to complete: {}", todo.description);
-            }
-            match queue.dequeue() {
-                Some(todo) => println!("Completed: [{}] {}", todo.id, todo.description),
-                None => println!("No tasks!"),
-            }
-            queue.save().unwrap();
+use cli::{Cli, Command, QueueCommand};
+use commands::Output;
+use error::CliError;
+
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        // Scripts asking for JSON get parse errors as JSON too. Help and
+        // version output are not errors and print as usual.
+        Err(e) if e.use_stderr() && std::env::args_os().any(|arg| arg == "--json") => {
+            return fail(&CliError::BadArgs(clap_message(&e)), true);
         }
+        Err(e) => e.exit(),
+    };
 
-        Some("list") => {
-            if queue.is_empty() {
-                println!("No tasks!");
-                return;
-            }
-            println!("{} task(s) pending:", queue.len());
-            for todo in queue.iter() {
-                println!("[{}] {}", todo.id, todo.description);
+    // Resolved once, so the daemon's socket is found next to the data file
+    // whichever directory the daemon and its clients were started from.
+    let result = absolute(&cli.file).and_then(|file| match cli.command {
+        Command::Serve => daemon::serve(&file).map(|()| None),
+        Command::Tui => tui::run(&file).map(|()| None),
+        Command::Queue(command) => run(command, &file).map(Some),
+    });
+
+    match result {
+        Ok(output) => {
+            if let Some(output) = output {
+                if cli.json {
+                    println!("{}", output.json);
+                } else {
+                    println!("{}", output.text);
+                }
             }
+            ExitCode::SUCCESS
         }
+        Err(e) => fail(&e, cli.json),
+    }
+}
 
-        _ => println!("Usage: todo <add|list|done|next>"),
+/// Reports `e` on stderr and returns its exit code.
+fn fail(e: &CliError, json: bool) -> ExitCode {
+    if json {
+        eprintln!("{}", json!({ "error": e.kind(), "message": e.to_string() }));
+    } else {
+        eprintln!("Error: {e}");
     }
+    ExitCode::from(e.exit_code() as u8)
+}
+
+/// A clap error on one line, without the usage and help hints after it.
+fn clap_message(e: &clap::Error) -> String {
+    let rendered = e.render().to_string();
+    let message: Vec<&str> = rendered
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .map(str::trim)
+        .collect();
+    let message = message.join(" ");
+    message
+        .strip_prefix("error: ")
+        .unwrap_or(&message)
+        .to_string()
+}
+
+/// Runs `command` through the daemon serving `file` if one is up, otherwise
+/// directly against the file.
+fn run(command: QueueCommand, file: &Path) -> Result<Output, CliError> {
+    // The daemon may have been started from another directory.
+    let command = match command {
+        QueueCommand::Merge { other } => QueueCommand::Merge {
+            other: absolute(&other)?,
+        },
+        command => command,
+    };
+    if let Some(response) = daemon::request(file, &command) {
+        return response;
+    }
+
+    let mut queue = migrate::open(file)?;
+    commands::execute(&mut queue, command)
+}
+
+fn absolute(path: &Path) -> Result<PathBuf, CliError> {
+    std::path::absolute(path).map_err(|e| CliError::Storage(e.into()))
 }
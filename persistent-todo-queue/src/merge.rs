@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+
+use persistent_todo_queue::queue::Queue;
+use serde::Serialize;
+
+use crate::deps;
+use crate::todo::{TaskId, Todo};
+
+const EDITED_AFTER_COMPLETION: &str = "edited after the other side completed it; kept completion";
+const EDITED_ON_BOTH: &str = "edited on both sides; kept local version";
+const DEPENDENCY_CYCLE: &str = "its prerequisites led back to it; cleared them";
+
+#[derive(Debug, Serialize)]
+pub struct Conflict {
+    pub id: TaskId,
+    pub reason: &'static str,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MergeReport {
+    /// Pending tasks that only existed in the other queue.
+    pub added: usize,
+    /// Local pending tasks that the other queue had already completed.
+    pub completed: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Merges `theirs` into `ours`, keeping our origin.
+///
+/// Pending tasks are unioned by `TaskId`, a completion on either side wins
+/// over a pending copy on the other, and the result is ordered FIFO by
+/// `created_at`. When both sides hold different copies of the same task the
+/// one with the higher `revision` wins; equal revisions mean both sides edited
+/// independently, so the local copy is kept and the task is reported. Tasks
+/// whose prerequisites lead back to themselves lose their prerequisites and are
+/// reported too.
+pub fn merge(ours: &Queue<Todo>, theirs: &Queue<Todo>) -> (Queue<Todo>, MergeReport) {
+    let mut report = MergeReport::default();
+
+    let mut completed: HashMap<TaskId, Todo> = HashMap::new();
+    for todo in ours.completed().iter().chain(theirs.completed()) {
+        completed.entry(todo.id).or_insert_with(|| todo.clone());
+    }
+
+    let mut pending: HashMap<TaskId, Todo> = HashMap::new();
+    for todo in ours.iter() {
+        match completed.get(&todo.id) {
+            Some(done) => {
+                report.completed += 1;
+                check_completion(done, todo, &mut report);
+            }
+            None => {
+                pending.insert(todo.id, todo.clone());
+            }
+        }
+    }
+    for todo in theirs.iter() {
+        if let Some(done) = completed.get(&todo.id) {
+            check_completion(done, todo, &mut report);
+            continue;
+        }
+        match pending.get_mut(&todo.id) {
+            None => {
+                report.added += 1;
+                pending.insert(todo.id, todo.clone());
+            }
+            Some(local) if local.same_content(todo) => {}
+            Some(local) if todo.revision > local.revision => *local = todo.clone(),
+            Some(local) if todo.revision < local.revision => {}
+            Some(_) => report.conflicts.push(Conflict {
+                id: todo.id,
+                reason: EDITED_ON_BOTH,
+            }),
+        }
+    }
+
+    // Neither side can build a cycle, but a hand-edited file can carry one,
+    // and its tasks would never be served.
+    for id in deps::cycles(pending.values()) {
+        if let Some(todo) = pending.get_mut(&id) {
+            todo.after.clear();
+        }
+        report.conflicts.push(Conflict {
+            id,
+            reason: DEPENDENCY_CYCLE,
+        });
+    }
+
+    let mut pending: Vec<Todo> = pending.into_values().collect();
+    pending.sort_by_key(|todo| (todo.created_at, todo.id));
+    let mut completed: Vec<Todo> = completed.into_values().collect();
+    completed.sort_by_key(|todo| (todo.completed_at, todo.id));
+
+    let mut merged = Queue::new();
+    merged.origin = ours.origin;
+    // Only matters when both files share an origin, e.g. one was copied from
+    // the other; taking the max stops the two counters from handing out the
+    // same id again.
+    merged.next_id = ours.next_id.max(theirs.next_id);
+    // The merged queue replaces ours on disk, so it carries on from our journal.
+    merged.seq = ours.seq;
+    for todo in pending {
+        merged.enqueue(todo);
+    }
+    for todo in completed {
+        merged.push_completed(todo);
+    }
+
+    report.conflicts.sort_by_key(|conflict| conflict.id);
+    (merged, report)
+}
+
+/// A completion wins over a pending copy, but flag it if that copy carries
+/// edits made after the completed version.
+fn check_completion(done: &Todo, pending: &Todo, report: &mut MergeReport) {
+    if pending.revision > done.revision {
+        report.conflicts.push(Conflict {
+            id: pending.id,
+            reason: EDITED_AFTER_COMPLETION,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo::FULL_HISTORY;
+
+    fn todo(queue: &Queue<Todo>, counter: u64, created_at: u64) -> Todo {
+        Todo {
+            id: TaskId {
+                origin: queue.origin,
+                counter,
+            },
+            description: format!("task {counter}"),
+            created_at,
+            due_at: None,
+            recurrence: None,
+            after: Vec::new(),
+            completed_at: None,
+            revision: 0,
+            sessions: Vec::new(),
+        }
+    }
+
+    fn done(mut todo: Todo) -> Todo {
+        todo.completed_at = Some(todo.created_at + 60);
+        todo
+    }
+
+    fn edited(mut todo: Todo, description: &str, revision: u32) -> Todo {
+        todo.description = description.into();
+        todo.revision = revision;
+        todo
+    }
+
+    /// Two queues with different origins, as on two machines.
+    fn replicas() -> (Queue<Todo>, Queue<Todo>) {
+        let ours = Queue::new();
+        let mut theirs = Queue::new();
+        theirs.origin = ours.origin.wrapping_add(1);
+        (ours, theirs)
+    }
+
+    fn ids<'a>(todos: impl IntoIterator<Item = &'a Todo>) -> Vec<TaskId> {
+        todos.into_iter().map(|todo| todo.id).collect()
+    }
+
+    #[test]
+    fn test_pending_tasks_are_unioned_in_fifo_order() {
+        let (mut ours, mut theirs) = replicas();
+        let (a, c) = (todo(&ours, 1, 10), todo(&ours, 2, 30));
+        let (b, d) = (todo(&theirs, 1, 20), todo(&theirs, 2, 40));
+        ours.extend([a.clone(), c.clone()]);
+        theirs.extend([d.clone(), a.clone(), b.clone()]);
+
+        let (merged, report) = merge(&ours, &theirs);
+        assert_eq!(ids(merged.iter()), ids([&a, &b, &c, &d]));
+        assert_eq!(report.added, 2);
+        assert_eq!(report.completed, 0);
+        assert!(report.conflicts.is_empty());
+        assert_eq!(merged.origin, ours.origin);
+    }
+
+    #[test]
+    fn test_completion_wins_over_pending() {
+        let (mut ours, mut theirs) = replicas();
+        let (local, remote) = (todo(&ours, 1, 10), todo(&theirs, 1, 20));
+        ours.enqueue(local.clone());
+        ours.push_completed(done(remote.clone()));
+        theirs.push_completed(done(local.clone()));
+        theirs.enqueue(remote.clone());
+
+        let (merged, report) = merge(&ours, &theirs);
+        assert!(merged.is_empty());
+        assert_eq!(ids(merged.completed()), ids([&local, &remote]));
+        assert_eq!(report.added, 0);
+        assert_eq!(report.completed, 1);
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_edit_after_completion_is_reported() {
+        let (mut ours, mut theirs) = replicas();
+        let task = todo(&ours, 1, 10);
+        ours.enqueue(edited(task.clone(), "renamed", 1));
+        theirs.push_completed(done(task.clone()));
+
+        let (merged, report) = merge(&ours, &theirs);
+        assert!(merged.is_empty());
+        assert_eq!(merged.completed()[0].description, "task 1");
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].id, task.id);
+        assert_eq!(report.conflicts[0].reason, EDITED_AFTER_COMPLETION);
+    }
+
+    #[test]
+    fn test_higher_revision_wins() {
+        let (mut ours, mut theirs) = replicas();
+        let (a, b) = (todo(&ours, 1, 10), todo(&ours, 2, 20));
+        ours.extend([edited(a.clone(), "ours", 2), edited(b.clone(), "ours", 1)]);
+        theirs.extend([edited(a, "theirs", 1), edited(b, "theirs", 2)]);
+
+        let (merged, report) = merge(&ours, &theirs);
+        let descriptions: Vec<&str> = merged.iter().map(|t| t.description.as_str()).collect();
+        assert_eq!(descriptions, ["ours", "theirs"]);
+        assert_eq!(report.added, 0);
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_equal_revisions_keep_ours_and_are_reported() {
+        let (mut ours, mut theirs) = replicas();
+        let task = todo(&ours, 1, 10);
+        ours.enqueue(edited(task.clone(), "ours", 1));
+        theirs.enqueue(edited(task.clone(), "theirs", 1));
+
+        let (merged, report) = merge(&ours, &theirs);
+        assert_eq!(merged.iter().next().unwrap().description, "ours");
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].id, task.id);
+        assert_eq!(report.conflicts[0].reason, EDITED_ON_BOTH);
+
+        // An identical copy on both sides is not a conflict.
+        let (_, report) = merge(&ours, &ours);
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_long_completed_task_is_not_revived() {
+        let (mut ours, mut theirs) = replicas();
+        let old = todo(&ours, 1, 10);
+        theirs.enqueue(old.clone());
+        ours.push_completed(done(old.clone()));
+        for counter in 2..FULL_HISTORY as u64 + 10 {
+            ours.push_completed(done(todo(&ours, counter, 10 + counter)));
+        }
+        ours.compact_completed(FULL_HISTORY, Todo::archive);
+        assert!(ours.completed()[0].description.is_empty());
+
+        let (merged, report) = merge(&ours, &theirs);
+        assert!(merged.is_empty());
+        assert_eq!(merged.completed().len(), ours.completed().len());
+        assert_eq!(report.added, 0);
+    }
+}
@@ -0,0 +1,154 @@
+//! Upgrading `todo.bin` files written before snapshots carried a format
+//! header. Those all have the original layout: a bare queue of tasks with a
+//! plain numeric id, a description and a creation time, and no journal.
+
+use std::error::Error;
+use std::path::Path;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use persistent_todo_queue::queue::{self, Queue};
+
+use crate::todo::{FULL_HISTORY, TaskId, Todo};
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct LegacyQueue {
+    inbox: Vec<LegacyTodo>,
+    outbox: Vec<LegacyTodo>,
+    next_id: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct LegacyTodo {
+    id: u64,
+    description: String,
+    created_at: u64,
+}
+
+/// Opens the queue at `file` like [`Queue::open`], rewriting it in the
+/// current format first if it still has the original layout. Completed tasks
+/// older than the last [`FULL_HISTORY`] are archived.
+pub fn open(file: &Path) -> Result<Queue<Todo>, Box<dyn Error>> {
+    let mut queue = match load_legacy(file)? {
+        Some(mut queue) => {
+            queue.save_to(file)?;
+            queue
+        }
+        None => Queue::open(file)?,
+    };
+    queue.compact_completed(FULL_HISTORY, Todo::archive);
+    Ok(queue)
+}
+
+/// Loads the queue at `file` like [`Queue::load_from`], upgrading the
+/// original layout in memory without touching the file. Completed tasks
+/// older than the last [`FULL_HISTORY`] are archived.
+pub fn load(file: &Path) -> Result<Queue<Todo>, Box<dyn Error>> {
+    let mut queue = match load_legacy(file)? {
+        Some(queue) => queue,
+        None => Queue::load_from(file)?,
+    };
+    queue.compact_completed(FULL_HISTORY, Todo::archive);
+    Ok(queue)
+}
+
+fn load_legacy(file: &Path) -> Result<Option<Queue<Todo>>, Box<dyn Error>> {
+    if !file.exists() || queue::is_versioned(file)? {
+        return Ok(None);
+    }
+    let legacy: LegacyQueue = borsh::from_slice(&std::fs::read(file)?)?;
+
+    // The new queue picks its origin, which the old ids are placed under.
+    let mut queue = Queue::new();
+    queue.next_id = legacy.next_id;
+    let origin = queue.origin;
+    for todo in legacy.outbox.into_iter().rev().chain(legacy.inbox) {
+        queue.enqueue(Todo {
+            id: TaskId {
+                origin,
+                counter: todo.id,
+            },
+            description: todo.description,
+            created_at: todo.created_at,
+            due_at: None,
+            recurrence: None,
+            after: Vec::new(),
+            completed_at: None,
+            revision: 0,
+            sessions: Vec::new(),
+        });
+    }
+    Ok(Some(queue))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("todo-migrate-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("todo.bin")
+    }
+
+    fn legacy_todo(id: u64, description: &str) -> LegacyTodo {
+        LegacyTodo {
+            id,
+            description: description.to_string(),
+            created_at: 1_700_000_000 + id,
+        }
+    }
+
+    #[test]
+    fn test_open_migrates_original_layout() {
+        let path = temp_path("open");
+        // Task 1 was already moved to the outbox by a `next` before the upgrade.
+        let legacy = LegacyQueue {
+            inbox: vec![legacy_todo(2, "do laundry"), legacy_todo(3, "cook dinner")],
+            outbox: vec![legacy_todo(1, "buy groceries")],
+            next_id: 4,
+        };
+        std::fs::write(&path, borsh::to_vec(&legacy).unwrap()).unwrap();
+        assert!(Queue::<Todo>::load_from(&path).is_err());
+
+        let queue = open(&path).unwrap();
+        let tasks: Vec<(u64, &str, u64)> = queue
+            .iter()
+            .map(|todo| (todo.id.counter, todo.description.as_str(), todo.created_at))
+            .collect();
+        assert_eq!(
+            tasks,
+            vec![
+                (1, "buy groceries", 1_700_000_001),
+                (2, "do laundry", 1_700_000_002),
+                (3, "cook dinner", 1_700_000_003),
+            ]
+        );
+        assert!(queue.iter().all(|todo| todo.id.origin == queue.origin));
+        assert_eq!(queue.next_id, 4);
+
+        // The file was rewritten in place and now opens directly.
+        assert!(queue::is_versioned(&path).unwrap());
+        let reopened: Queue<Todo> = Queue::open(&path).unwrap();
+        assert_eq!(reopened.origin, queue.origin);
+        assert_eq!(reopened.len(), 3);
+    }
+
+    #[test]
+    fn test_load_leaves_original_file() {
+        let path = temp_path("load");
+        let legacy = LegacyQueue {
+            inbox: vec![legacy_todo(1, "buy groceries")],
+            outbox: Vec::new(),
+            next_id: 2,
+        };
+        let bytes = borsh::to_vec(&legacy).unwrap();
+        std::fs::write(&path, &bytes).unwrap();
+
+        let queue = load(&path).unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
+    }
+}
@@ -1,18 +1,57 @@
+use std::collections::hash_map::RandomState;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{ErrorKind, Read};
+use std::iter::{Chain, Rev};
+use std::path::{Path, PathBuf};
+
 use borsh::{BorshDeserialize, BorshSerialize};
 
+use crate::format::{self, SNAPSHOT_MAGIC};
+use crate::journal::{self, Op, Record};
+
+// Once this many ops have been appended since the last snapshot, `record`
+// folds the journal into a fresh snapshot so replay on load stays short.
+const SNAPSHOT_INTERVAL: u64 = 128;
+
 #[derive(Debug, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Queue<T> {
     inbox: Vec<T>,
     outbox: Vec<T>,
+    completed: Vec<T>,
     pub next_id: u64,
+    /// Random id of the replica that created this queue. Together with
+    /// `next_id` it makes item ids unique across machines.
+    pub origin: u32,
+    /// Sequence number of the last journal record applied to this queue.
+    /// Replay skips records at or below it, so a journal that outlived the
+    /// snapshot it was folded into is not applied twice.
+    pub seq: u64,
+    #[borsh(skip)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    journal_len: u64,
+    /// Snapshot file this queue was opened from or last saved to.
+    #[borsh(skip)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    path: Option<PathBuf>,
 }
 
 impl<T> Queue<T> {
     pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
         Queue {
-            inbox: Vec::new(),
+            inbox: Vec::with_capacity(capacity),
             outbox: Vec::new(),
+            completed: Vec::new(),
             next_id: 1,
+            origin: random_origin(),
+            seq: 0,
+            journal_len: 0,
+            path: None,
         }
     }
 
@@ -46,8 +85,126 @@ impl<T> Queue<T> {
         self.inbox.is_empty() && self.outbox.is_empty()
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &T> {
-        self.outbox.iter().rev().chain(self.inbox.iter())
+    /// Iterates all items in FIFO order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.outbox.iter().rev().chain(self.inbox.iter()),
+        }
+    }
+
+    /// Removes all items, returning them in FIFO order.
+    pub fn drain(&mut self) -> IntoIter<T> {
+        IntoIter {
+            inner: std::mem::take(&mut self.outbox)
+                .into_iter()
+                .rev()
+                .chain(std::mem::take(&mut self.inbox)),
+        }
+    }
+
+    /// Keeps only the items for which `f` returns true, visiting them in FIFO
+    /// order and preserving the order of the ones kept.
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        // The outbox is stored back to front; flip it so `f` sees FIFO order.
+        self.outbox.reverse();
+        self.outbox.retain(&mut f);
+        self.inbox.retain(&mut f);
+        self.outbox.reverse();
+    }
+
+    /// Every completed item, in the order they were completed.
+    pub fn completed(&self) -> &[T] {
+        &self.completed
+    }
+
+    /// Appends `item` to the completed history.
+    pub fn push_completed(&mut self, item: T) {
+        self.completed.push(item);
+    }
+
+    /// Runs `f` on every completed item except the newest `keep`, oldest
+    /// first. Nothing is ever dropped from the history, so this is how callers
+    /// shrink old entries down to the parts they still need.
+    pub fn compact_completed(&mut self, keep: usize, f: impl FnMut(&mut T)) {
+        let end = self.completed.len().saturating_sub(keep);
+        self.completed[..end].iter_mut().for_each(f);
+    }
+
+    /// Returns the item at `index` in FIFO order.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let outbox_len = self.outbox.len();
+        if index < outbox_len {
+            self.outbox.get_mut(outbox_len - 1 - index)
+        } else {
+            self.inbox.get_mut(index - outbox_len)
+        }
+    }
+
+    /// Removes and returns the item at `index` in FIFO order.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        let outbox_len = self.outbox.len();
+        if index < outbox_len {
+            Some(self.outbox.remove(outbox_len - 1 - index))
+        } else if index - outbox_len < self.inbox.len() {
+            Some(self.inbox.remove(index - outbox_len))
+        } else {
+            None
+        }
+    }
+
+    /// Inserts `item` at `index` in FIFO order, shifting later items back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, item: T) {
+        let outbox_len = self.outbox.len();
+        if index <= outbox_len {
+            self.outbox.insert(outbox_len - index, item);
+        } else {
+            self.inbox.insert(index - outbox_len, item);
+        }
+    }
+
+    /// Moves the item at `from` to position `to` in FIFO order. Does nothing
+    /// if either index is out of range.
+    pub fn move_item(&mut self, from: usize, to: usize) {
+        if from >= self.len() || to >= self.len() {
+            return;
+        }
+        if let Some(item) = self.remove(from) {
+            self.insert(to, item);
+        }
+    }
+
+    pub fn apply(&mut self, op: Op<T>) {
+        match op {
+            Op::Enqueue(item) => {
+                self.enqueue(item);
+                self.next_id += 1;
+            }
+            Op::Dequeue => {
+                self.dequeue();
+            }
+            Op::Edit { index, item } => {
+                if let Some(slot) = self.get_mut(index as usize) {
+                    *slot = item;
+                }
+            }
+            Op::Remove { index } => {
+                self.remove(index as usize);
+            }
+            Op::Complete { index, item } => {
+                if self.remove(index as usize).is_some() {
+                    self.push_completed(item);
+                }
+            }
+            Op::Move { from, to } => self.move_item(from as usize, to as usize),
+            Op::CompleteAndEnqueue { index, item, next } => {
+                self.apply(Op::Complete { index, item });
+                self.apply(Op::Enqueue(next));
+            }
+        }
     }
 }
 
@@ -56,15 +213,197 @@ where
     T: BorshSerialize,
     T: BorshDeserialize,
 {
-    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let bytes = borsh::to_vec(self)?;
-        std::fs::write("todo.bin", &bytes)?;
-        Ok(())
+    /// Opens the queue stored at `path`, creating it if it does not exist yet.
+    pub fn open(path: impl AsRef<Path>) -> Result<Queue<T>, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        if path.exists() {
+            return Self::load_from(path);
+        }
+        // First run: write a snapshot straight away so the randomly chosen
+        // origin is fixed before any ids are handed out under it.
+        let mut queue = Queue::new();
+        queue.replay(path)?;
+        queue.save_to(path)?;
+        Ok(queue)
     }
 
-    pub fn load() -> Result<Queue<T>, Box<dyn std::error::Error>> {
-        let bytes = std::fs::read("todo.bin")?;
-        let queue = borsh::from_slice(&bytes)?;
+    /// Reads the snapshot at `path` and replays the journal stored next to it.
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Queue<T>, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)?;
+        let body = format::strip(&bytes, SNAPSHOT_MAGIC)?
+            .ok_or("snapshot has no format header; it was written by an older version")?;
+        let mut queue: Queue<T> = borsh::from_slice(body)?;
+        queue.replay(path)?;
+        queue.path = Some(path.to_path_buf());
         Ok(queue)
     }
+
+    /// Applies the records in the journal next to `path` that are newer than
+    /// the queue.
+    fn replay(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        for record in journal::read(journal_path(path))? {
+            if record.seq <= self.seq {
+                continue;
+            }
+            self.apply(record.op);
+            self.seq = record.seq;
+            self.journal_len += 1;
+        }
+        Ok(())
+    }
+
+    /// Appends `op` to the journal next to the queue's snapshot, then applies it in memory.
+    pub fn record(&mut self, op: Op<T>) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.path.clone().ok_or("queue has no backing file")?;
+        let record = Record {
+            seq: self.seq + 1,
+            op,
+        };
+        journal::append(journal_path(&path), &record)?;
+        self.apply(record.op);
+        self.seq = record.seq;
+        self.journal_len += 1;
+        if self.journal_len >= SNAPSHOT_INTERVAL {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Writes a full snapshot to the file the queue was opened from and
+    /// truncates the journal it supersedes.
+    pub fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.path.clone().ok_or("queue has no backing file")?;
+        self.save_to(path)
+    }
+
+    /// Writes a full snapshot to `path`, truncates the journal next to it and
+    /// makes `path` the queue's backing file.
+    ///
+    /// The snapshot records `seq`, so if the process dies before the journal is
+    /// truncated the next load skips the records it already contains.
+    pub fn save_to(&mut self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        let mut bytes = format::header(SNAPSHOT_MAGIC).to_vec();
+        borsh::to_writer(&mut bytes, self)?;
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, &bytes)?;
+        std::fs::rename(&tmp, path)?;
+        journal::clear(journal_path(path))?;
+        self.journal_len = 0;
+        self.path = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Snapshot file backing this queue, if it was opened from or saved to one.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+}
+
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for Queue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut queue = Queue::new();
+        queue.extend(iter);
+        queue
+    }
+}
+
+impl<T> Extend<T> for Queue<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.inbox.extend(iter);
+    }
+}
+
+impl<T> IntoIterator for Queue<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(mut self) -> IntoIter<T> {
+        self.drain()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Queue<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+/// Borrowing FIFO iterator returned by [`Queue::iter`].
+pub struct Iter<'a, T> {
+    inner: Chain<Rev<std::slice::Iter<'a, T>>, std::slice::Iter<'a, T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+/// Owning FIFO iterator returned by [`Queue::drain`] and `into_iter`.
+pub struct IntoIter<T> {
+    inner: Chain<Rev<std::vec::IntoIter<T>>, std::vec::IntoIter<T>>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.inner.next_back()
+    }
+}
+
+/// Whether the snapshot at `path` starts with a format header. Snapshots
+/// written before the header existed do not, and `Queue::load_from` refuses
+/// them so the caller can migrate them first.
+pub fn is_versioned(path: impl AsRef<Path>) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut magic = [0; 4];
+    match File::open(path)?.read_exact(&mut magic) {
+        Ok(()) => Ok(magic == SNAPSHOT_MAGIC),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn journal_path(snapshot: &Path) -> PathBuf {
+    snapshot.with_extension("journal")
+}
+
+fn random_origin() -> u32 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    hasher.finish() as u32
 }
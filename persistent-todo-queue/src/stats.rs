@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+
+use persistent_todo_queue::queue::Queue;
+use serde::Serialize;
+
+use crate::todo::{TaskId, Todo};
+
+const DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    /// Completion counts keyed by UTC date (`YYYY-MM-DD`).
+    pub completed_per_day: BTreeMap<String, usize>,
+    /// Mean seconds from `created_at` to `completed_at`, `None` if nothing is completed.
+    pub average_completion_secs: Option<u64>,
+    pub tracked: Vec<Tracked>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Tracked {
+    pub id: TaskId,
+    pub description: String,
+    pub secs: u64,
+}
+
+pub fn compute(queue: &Queue<Todo>, now: u64) -> Stats {
+    let mut completed_per_day = BTreeMap::new();
+    let mut total_secs = 0;
+    let mut count = 0;
+    for todo in queue.completed() {
+        if let Some(completed_at) = todo.completed_at {
+            *completed_per_day.entry(date(completed_at)).or_insert(0) += 1;
+            total_secs += completed_at.saturating_sub(todo.created_at);
+            count += 1;
+        }
+    }
+
+    let tracked = queue
+        .completed()
+        .iter()
+        .chain(queue.iter())
+        .filter(|todo| !todo.sessions.is_empty())
+        .map(|todo| Tracked {
+            id: todo.id,
+            description: todo.description.clone(),
+            secs: todo.tracked_secs(now),
+        })
+        .collect();
+
+    Stats {
+        completed_per_day,
+        average_completion_secs: (count > 0).then(|| total_secs / count),
+        tracked,
+    }
+}
+
+/// Formats a duration as its two largest units, e.g. `2h 5m` or `3d 4h`.
+pub fn format_duration(secs: u64) -> String {
+    let (d, h, m, s) = (secs / DAY, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    match (d, h, m) {
+        (0, 0, 0) => format!("{s}s"),
+        (0, 0, _) => format!("{m}m {s}s"),
+        (0, _, _) => format!("{h}h {m}m"),
+        _ => format!("{d}d {h}h"),
+    }
+}
+
+/// UTC calendar date of a unix timestamp, without pulling in a date crate.
+/// Uses the days-to-civil conversion from Howard Hinnant's date algorithms.
+fn date(timestamp: u64) -> String {
+    let z = (timestamp / DAY) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo::Session;
+
+    // 2024-03-10T00:00:00Z.
+    const MIDNIGHT: u64 = 1_710_028_800;
+
+    fn todo(queue: &Queue<Todo>, counter: u64, created_at: u64) -> Todo {
+        Todo {
+            id: TaskId {
+                origin: queue.origin,
+                counter,
+            },
+            description: format!("task {counter}"),
+            created_at,
+            due_at: None,
+            recurrence: None,
+            after: Vec::new(),
+            completed_at: None,
+            revision: 0,
+            sessions: Vec::new(),
+        }
+    }
+
+    fn complete(queue: &mut Queue<Todo>, counter: u64, created_at: u64, completed_at: u64) {
+        let mut todo = todo(queue, counter, created_at);
+        todo.completed_at = Some(completed_at);
+        queue.push_completed(todo);
+    }
+
+    #[test]
+    fn test_days_split_at_utc_midnight() {
+        let mut queue = Queue::new();
+        complete(&mut queue, 1, MIDNIGHT - 3600, MIDNIGHT - 1);
+        complete(&mut queue, 2, MIDNIGHT - 3600, MIDNIGHT);
+        complete(&mut queue, 3, MIDNIGHT - 3600, MIDNIGHT + DAY - 1);
+
+        let stats = compute(&queue, MIDNIGHT + DAY);
+        let days: Vec<(&str, usize)> = stats
+            .completed_per_day
+            .iter()
+            .map(|(day, count)| (day.as_str(), *count))
+            .collect();
+        assert_eq!(days, [("2024-03-09", 1), ("2024-03-10", 2)]);
+    }
+
+    #[test]
+    fn test_average_completion() {
+        let mut queue = Queue::new();
+        assert_eq!(compute(&queue, MIDNIGHT).average_completion_secs, None);
+
+        // Pending tasks do not count towards the average.
+        let pending = todo(&queue, 1, MIDNIGHT - DAY);
+        queue.enqueue(pending);
+        assert_eq!(compute(&queue, MIDNIGHT).average_completion_secs, None);
+
+        complete(&mut queue, 2, MIDNIGHT, MIDNIGHT + 60);
+        complete(&mut queue, 3, MIDNIGHT, MIDNIGHT + 180);
+        let stats = compute(&queue, MIDNIGHT + DAY);
+        assert_eq!(stats.average_completion_secs, Some(120));
+    }
+
+    #[test]
+    fn test_running_session_counts_up_to_now() {
+        let mut queue = Queue::new();
+        let mut finished = todo(&queue, 1, MIDNIGHT);
+        finished.completed_at = Some(MIDNIGHT + 900);
+        finished.sessions.push(Session {
+            started_at: MIDNIGHT,
+            stopped_at: Some(MIDNIGHT + 600),
+        });
+        queue.push_completed(finished);
+
+        let mut running = todo(&queue, 2, MIDNIGHT);
+        running.sessions = vec![
+            Session {
+                started_at: MIDNIGHT,
+                stopped_at: Some(MIDNIGHT + 60),
+            },
+            Session {
+                started_at: MIDNIGHT + 100,
+                stopped_at: None,
+            },
+        ];
+        queue.enqueue(running);
+        queue.enqueue(todo(&queue, 3, MIDNIGHT));
+
+        let stats = compute(&queue, MIDNIGHT + 400);
+        let tracked: Vec<(u64, u64)> = stats
+            .tracked
+            .iter()
+            .map(|t| (t.id.counter, t.secs))
+            .collect();
+        assert_eq!(tracked, [(1, 600), (2, 360)]);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(59), "59s");
+        assert_eq!(format_duration(60), "1m 0s");
+        assert_eq!(format_duration(3599), "59m 59s");
+        assert_eq!(format_duration(3600), "1h 0m");
+        assert_eq!(format_duration(2 * 3600 + 5 * 60 + 30), "2h 5m");
+        assert_eq!(format_duration(DAY - 1), "23h 59m");
+        assert_eq!(format_duration(3 * DAY + 4 * 3600 + 59), "3d 4h");
+    }
+
+    #[test]
+    fn test_date() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(951_782_400), "2000-02-29");
+        assert_eq!(date(MIDNIGHT - 1), "2024-03-09");
+        assert_eq!(date(MIDNIGHT), "2024-03-10");
+    }
+}
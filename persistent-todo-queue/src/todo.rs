@@ -1,8 +1,308 @@
+use std::fmt;
+use std::str::FromStr;
+
 use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// Completed tasks kept whole. Older ones are archived down to their id and
+/// timestamps, which is all merges, prerequisites and completion stats need.
+pub const FULL_HISTORY: usize = 1000;
 
-#[derive(Debug, BorshSerialize, BorshDeserialize)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Serialize)]
 pub struct Todo {
-    pub id: u64,
+    pub id: TaskId,
     pub description: String,
     pub created_at: u64,
+    pub due_at: Option<u64>,
+    pub recurrence: Option<Recurrence>,
+    pub after: Vec<TaskId>,
+    pub completed_at: Option<u64>,
+    /// Bumped on every edit so merges can tell a newer copy from a stale one.
+    pub revision: u32,
+    pub sessions: Vec<Session>,
+}
+
+/// A span of tracked work on a task. `stopped_at` is `None` while running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize, Serialize)]
+pub struct Session {
+    pub started_at: u64,
+    pub stopped_at: Option<u64>,
+}
+
+/// Globally unique task identity: the origin of the queue that created the
+/// task plus that queue's counter.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+)]
+pub struct TaskId {
+    pub origin: u32,
+    pub counter: u64,
+}
+
+impl TaskId {
+    /// Short form for display: tasks from the local queue show just the
+    /// counter, tasks merged in from elsewhere show `origin:counter`.
+    pub fn label(&self, local_origin: u32) -> String {
+        if self.origin == local_origin {
+            self.counter.to_string()
+        } else {
+            format!("{:08x}:{}", self.origin, self.counter)
+        }
+    }
+}
+
+/// A task id as typed on the command line, `12` or `1a2b3c4d:12`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TaskRef {
+    pub origin: Option<u32>,
+    pub counter: u64,
+}
+
+impl TaskRef {
+    pub fn resolve(&self, local_origin: u32) -> TaskId {
+        TaskId {
+            origin: self.origin.unwrap_or(local_origin),
+            counter: self.counter,
+        }
+    }
+}
+
+impl FromStr for TaskRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected `<N>` or `<origin>:<N>`, got `{s}`");
+        match s.split_once(':') {
+            Some((origin, counter)) => Ok(TaskRef {
+                origin: Some(u32::from_str_radix(origin, 16).map_err(|_| invalid())?),
+                counter: counter.parse().map_err(|_| invalid())?,
+            }),
+            None => Ok(TaskRef {
+                origin: None,
+                counter: s.parse().map_err(|_| invalid())?,
+            }),
+        }
+    }
+}
+
+impl Todo {
+    /// Builds the follow-up instance of a recurring task, or `None` if it does not repeat.
+    ///
+    /// The next due time steps forward from the current one by whole intervals until it
+    /// lands in the future, so a daily task completed three days late is due tomorrow
+    /// rather than three times today. A zero interval, which the parser rejects but an
+    /// old or merged file may still hold, does not repeat.
+    pub fn next_instance(&self, id: TaskId, now: u64) -> Option<Todo> {
+        let recurrence = self.recurrence?;
+        let interval = recurrence.interval_secs();
+        if interval == 0 {
+            return None;
+        }
+        let mut due_at = self.due_at.unwrap_or(now);
+        if due_at <= now {
+            let steps = (now - due_at) / interval + 1;
+            due_at = due_at.saturating_add(steps.saturating_mul(interval));
+        }
+        Some(Todo {
+            id,
+            description: self.description.clone(),
+            created_at: now,
+            due_at: Some(due_at),
+            recurrence: Some(recurrence),
+            after: Vec::new(),
+            completed_at: None,
+            revision: 0,
+            sessions: Vec::new(),
+        })
+    }
+
+    /// Drops what an old completed task no longer needs, keeping its id and
+    /// timestamps.
+    pub fn archive(&mut self) {
+        self.description = String::new();
+        self.recurrence = None;
+        self.after = Vec::new();
+        self.sessions = Vec::new();
+    }
+
+    pub fn is_tracking(&self) -> bool {
+        self.sessions.last().is_some_and(|s| s.stopped_at.is_none())
+    }
+
+    /// Closes the running session, if any. Returns true if one was running.
+    pub fn stop_tracking(&mut self, now: u64) -> bool {
+        match self.sessions.last_mut() {
+            Some(session) if session.stopped_at.is_none() => {
+                session.stopped_at = Some(now);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Total tracked seconds, counting a running session up to `now`.
+    pub fn tracked_secs(&self, now: u64) -> u64 {
+        self.sessions
+            .iter()
+            .map(|s| s.stopped_at.unwrap_or(now).saturating_sub(s.started_at))
+            .sum()
+    }
+
+    /// True if both are the same task with the same content, ignoring completion
+    /// and revision.
+    pub fn same_content(&self, other: &Todo) -> bool {
+        self.id == other.id
+            && self.description == other.description
+            && self.due_at == other.due_at
+            && self.recurrence == other.recurrence
+            && self.after == other.after
+            && self.sessions == other.sessions
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    Hours(u32),
+}
+
+impl Recurrence {
+    pub fn interval_secs(&self) -> u64 {
+        match self {
+            Recurrence::Daily => DAY,
+            Recurrence::Weekly => WEEK,
+            Recurrence::Hours(n) => *n as u64 * HOUR,
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Recurrence::Daily),
+            "week" => Ok(Recurrence::Weekly),
+            _ => match s.strip_suffix('h').map(str::parse::<u32>) {
+                Some(Ok(n)) if n > 0 => Ok(Recurrence::Hours(n)),
+                _ => Err(format!("expected `day`, `week` or `<N>h`, got `{s}`")),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "every day"),
+            Recurrence::Weekly => write!(f, "every week"),
+            Recurrence::Hours(n) => write!(f, "every {n}h"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: TaskId = TaskId {
+        origin: 1,
+        counter: 2,
+    };
+    const NOW: u64 = 1_700_000_000;
+
+    fn recurring(due_at: Option<u64>, recurrence: Recurrence) -> Todo {
+        Todo {
+            id: TaskId {
+                origin: 1,
+                counter: 1,
+            },
+            description: "water plants".to_string(),
+            created_at: NOW - WEEK,
+            due_at,
+            recurrence: Some(recurrence),
+            after: Vec::new(),
+            completed_at: Some(NOW),
+            revision: 3,
+            sessions: Vec::new(),
+        }
+    }
+
+    fn next_due(todo: &Todo, now: u64) -> Option<u64> {
+        todo.next_instance(ID, now)?.due_at
+    }
+
+    #[test]
+    fn test_next_due() {
+        // Completed on time: due one interval later.
+        let todo = recurring(Some(NOW), Recurrence::Daily);
+        assert_eq!(next_due(&todo, NOW), Some(NOW + DAY));
+        assert_eq!(next_due(&todo, NOW + DAY - 1), Some(NOW + DAY));
+
+        // Completed before it was due: the due time stays.
+        assert_eq!(next_due(&todo, NOW - 1), Some(NOW));
+
+        // No due time yet counts from now.
+        let todo = recurring(None, Recurrence::Hours(6));
+        assert_eq!(next_due(&todo, NOW), Some(NOW + 6 * HOUR));
+
+        let next = recurring(Some(NOW), Recurrence::Weekly)
+            .next_instance(ID, NOW)
+            .unwrap();
+        assert_eq!(next.id, ID);
+        assert_eq!(next.created_at, NOW);
+        assert_eq!(next.due_at, Some(NOW + WEEK));
+        assert_eq!(next.recurrence, Some(Recurrence::Weekly));
+        assert_eq!((next.completed_at, next.revision), (None, 0));
+    }
+
+    #[test]
+    fn test_next_due_catches_up() {
+        // Three and a half days late: due the next day boundary, not three times today.
+        let todo = recurring(Some(NOW), Recurrence::Daily);
+        assert_eq!(
+            next_due(&todo, NOW + 3 * DAY + DAY / 2),
+            Some(NOW + 4 * DAY)
+        );
+        // Exactly on a boundary moves past it.
+        assert_eq!(next_due(&todo, NOW + 3 * DAY), Some(NOW + 4 * DAY));
+
+        // Years overdue is one step, not one loop iteration per interval.
+        let todo = recurring(Some(0), Recurrence::Hours(1));
+        assert_eq!(next_due(&todo, NOW), Some(NOW / HOUR * HOUR + HOUR));
+    }
+
+    #[test]
+    fn test_zero_interval_does_not_repeat() {
+        let todo = recurring(Some(NOW), Recurrence::Hours(0));
+        assert!(todo.next_instance(ID, NOW + DAY).is_none());
+    }
+
+    #[test]
+    fn test_parse_every() {
+        assert_eq!("day".parse(), Ok(Recurrence::Daily));
+        assert_eq!("week".parse(), Ok(Recurrence::Weekly));
+        assert_eq!("12h".parse(), Ok(Recurrence::Hours(12)));
+        for invalid in ["0h", "h", "-1h", "1.5h", "12", "days", ""] {
+            assert!(invalid.parse::<Recurrence>().is_err(), "{invalid}");
+        }
+    }
 }
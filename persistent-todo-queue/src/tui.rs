@@ -0,0 +1,460 @@
+use std::path::Path;
+
+use persistent_todo_queue::journal::Op;
+use persistent_todo_queue::queue::Queue;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::commands;
+use crate::daemon;
+use crate::deps;
+use crate::error::CliError;
+use crate::migrate;
+use crate::todo::Todo;
+
+const HELP: &str = "a add  e edit  d done  J/K move  / filter  q quit";
+
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Normal,
+    Add(String),
+    Edit(String),
+    Filter,
+}
+
+/// State of the full-screen view. Every mutation is applied to the queue and
+/// saved straight away, so quitting never loses anything.
+pub struct App {
+    queue: Queue<Todo>,
+    /// Row of the highlighted task among the visible (filtered) tasks.
+    selected: usize,
+    filter: String,
+    mode: Mode,
+    status: String,
+    quit: bool,
+}
+
+/// Runs the TUI against the queue at `file` until the user quits.
+pub fn run(file: &Path) -> Result<(), CliError> {
+    if daemon::is_running(file) {
+        return Err(CliError::BadArgs(format!(
+            "a daemon is serving {}; stop it before opening the TUI",
+            file.display()
+        )));
+    }
+    let mut app = App::new(migrate::open(file)?);
+    let mut terminal = ratatui::try_init().map_err(|e| CliError::Storage(e.into()))?;
+    let result = app.event_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App {
+    pub fn new(queue: Queue<Todo>) -> Self {
+        App {
+            queue,
+            selected: 0,
+            filter: String::new(),
+            mode: Mode::Normal,
+            status: String::new(),
+            quit: false,
+        }
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<(), CliError> {
+        while !self.quit {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(|e| CliError::Storage(e.into()))?;
+            if let Event::Key(key) = event::read().map_err(|e| CliError::Storage(e.into()))?
+                && key.kind == KeyEventKind::Press
+            {
+                self.handle_key(key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Queue indices of the tasks matching the filter, in FIFO order.
+    fn visible(&self) -> Vec<usize> {
+        let filter = self.filter.to_lowercase();
+        self.queue
+            .iter()
+            .enumerate()
+            .filter(|(_, todo)| todo.description.to_lowercase().contains(&filter))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Queue index of the highlighted task, if any.
+    fn selected_index(&self) -> Option<usize> {
+        self.visible().get(self.selected).copied()
+    }
+
+    /// Handles one key press. Only storage errors are returned; anything else
+    /// is shown on the status line.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Result<(), CliError> {
+        self.status.clear();
+        let result = match self.mode {
+            Mode::Normal => self.normal_key(key.code),
+            _ => self.input_key(key.code),
+        };
+        let visible = self.visible().len();
+        self.selected = self.selected.min(visible.saturating_sub(1));
+        match result {
+            Err(CliError::Storage(e)) => Err(CliError::Storage(e)),
+            Err(e) => {
+                self.status = e.to_string();
+                Ok(())
+            }
+            Ok(()) => Ok(()),
+        }
+    }
+
+    fn normal_key(&mut self, code: KeyCode) -> Result<(), CliError> {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.selected += 1,
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('a') => self.mode = Mode::Add(String::new()),
+            KeyCode::Char('/') => self.mode = Mode::Filter,
+            KeyCode::Char('e') => {
+                if let Some(index) = self.selected_index()
+                    && let Some(todo) = self.queue.iter().nth(index)
+                {
+                    self.mode = Mode::Edit(todo.description.clone());
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Enter => self.complete()?,
+            KeyCode::Char('J') => self.move_selected(1)?,
+            KeyCode::Char('K') => self.move_selected(-1)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn input_key(&mut self, code: KeyCode) -> Result<(), CliError> {
+        let mode = std::mem::replace(&mut self.mode, Mode::Normal);
+        match (mode, code) {
+            (Mode::Filter, KeyCode::Esc) => self.filter.clear(),
+            (Mode::Filter, KeyCode::Enter) => {}
+            (Mode::Filter, KeyCode::Backspace) => {
+                self.filter.pop();
+                self.mode = Mode::Filter;
+            }
+            (Mode::Filter, KeyCode::Char(c)) => {
+                self.filter.push(c);
+                self.mode = Mode::Filter;
+            }
+            (_, KeyCode::Esc) => {}
+            (Mode::Add(text), KeyCode::Enter) => {
+                let todo = commands::new_todo(&self.queue, text, None, &[])?;
+                self.status = format!("Added [{}]", todo.id.label(self.queue.origin));
+                self.commit(Op::Enqueue(todo))?;
+                self.selected = self.visible().len().saturating_sub(1);
+            }
+            (Mode::Edit(text), KeyCode::Enter) => self.edit(text)?,
+            (Mode::Add(mut text), KeyCode::Backspace) => {
+                text.pop();
+                self.mode = Mode::Add(text);
+            }
+            (Mode::Edit(mut text), KeyCode::Backspace) => {
+                text.pop();
+                self.mode = Mode::Edit(text);
+            }
+            (Mode::Add(mut text), KeyCode::Char(c)) => {
+                text.push(c);
+                self.mode = Mode::Add(text);
+            }
+            (Mode::Edit(mut text), KeyCode::Char(c)) => {
+                text.push(c);
+                self.mode = Mode::Edit(text);
+            }
+            (mode, _) => self.mode = mode,
+        }
+        Ok(())
+    }
+
+    fn edit(&mut self, description: String) -> Result<(), CliError> {
+        commands::check_description(&description)?;
+        let index = self.selected_index().ok_or(CliError::Empty)?;
+        let mut todo = self
+            .queue
+            .iter()
+            .nth(index)
+            .cloned()
+            .ok_or(CliError::Empty)?;
+        todo.description = description;
+        todo.revision += 1;
+        self.status = format!("Updated [{}]", todo.id.label(self.queue.origin));
+        self.commit(Op::Edit {
+            index: index as u64,
+            item: todo,
+        })
+    }
+
+    fn complete(&mut self) -> Result<(), CliError> {
+        let index = self.selected_index().ok_or(CliError::Empty)?;
+        let origin = self.queue.origin;
+        if let Some(todo) = self.queue.iter().nth(index) {
+            let blockers = deps::blockers(&self.queue, todo);
+            if !blockers.is_empty() {
+                let ids: Vec<String> = blockers.iter().map(|id| id.label(origin)).collect();
+                return Err(CliError::BadArgs(format!("blocked by {}", ids.join(", "))));
+            }
+        }
+        let (todo, next) = commands::complete(&self.queue, index)?;
+        self.status = format!("Completed [{}] {}", todo.id.label(origin), todo.description);
+        self.commit(match next {
+            Some(next) => Op::CompleteAndEnqueue {
+                index: index as u64,
+                item: todo,
+                next,
+            },
+            None => Op::Complete {
+                index: index as u64,
+                item: todo,
+            },
+        })
+    }
+
+    /// Swaps the highlighted task with its visible neighbour `offset` rows away.
+    /// Tasks hidden by the filter between the two keep their place.
+    fn move_selected(&mut self, offset: isize) -> Result<(), CliError> {
+        let visible = self.visible();
+        let target = self.selected.checked_add_signed(offset);
+        let (Some(&from), Some(&to)) = (
+            visible.get(self.selected),
+            target.and_then(|target| visible.get(target)),
+        ) else {
+            return Ok(());
+        };
+        // Moving the task next to its neighbour shifts the neighbour by one
+        // towards `from`; the second move puts it where the task was.
+        let neighbour = if from < to { to - 1 } else { to + 1 };
+        self.queue.apply(Op::Move {
+            from: from as u64,
+            to: to as u64,
+        });
+        self.queue.apply(Op::Move {
+            from: neighbour as u64,
+            to: from as u64,
+        });
+        self.save()?;
+        self.selected = target.unwrap_or(self.selected);
+        Ok(())
+    }
+
+    fn commit(&mut self, op: Op<Todo>) -> Result<(), CliError> {
+        self.queue.apply(op);
+        self.save()
+    }
+
+    fn save(&mut self) -> Result<(), CliError> {
+        self.queue.save()?;
+        Ok(())
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let [list_area, prompt_area, help_area] = Layout::vertical([
+            Constraint::Min(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let visible = self.visible();
+        let items: Vec<String> = visible
+            .iter()
+            .filter_map(|&index| self.queue.iter().nth(index))
+            .map(|todo| commands::list_line(&self.queue, todo))
+            .collect();
+        let title = if self.filter.is_empty() {
+            format!(" todo: {} pending ", self.queue.len())
+        } else {
+            format!(
+                " todo: {} of {} matching \"{}\" ",
+                visible.len(),
+                self.queue.len(),
+                self.filter
+            )
+        };
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        let mut state = ListState::default();
+        if !visible.is_empty() {
+            state.select(Some(self.selected));
+        }
+        frame.render_stateful_widget(list, list_area, &mut state);
+
+        let prompt = match &self.mode {
+            Mode::Normal => self.status.clone(),
+            Mode::Add(text) => format!("Add: {text}_"),
+            Mode::Edit(text) => format!("Edit: {text}_"),
+            Mode::Filter => format!("/{}_", self.filter),
+        };
+        frame.render_widget(Paragraph::new(prompt), prompt_area);
+        frame.render_widget(
+            Paragraph::new(Line::from(HELP).style(Style::new().add_modifier(Modifier::DIM))),
+            help_area,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::KeyModifiers;
+
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("todo-tui-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("todo.bin")
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+            .unwrap();
+    }
+
+    fn add(app: &mut App, description: &str) {
+        press(app, KeyCode::Char('a'));
+        for c in description.chars() {
+            press(app, KeyCode::Char(c));
+        }
+        press(app, KeyCode::Enter);
+    }
+
+    fn render(app: &App) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(50, 7)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    /// A list row inside the 50 column wide border.
+    fn row(text: &str) -> String {
+        format!("│{text:<48}│")
+    }
+
+    fn descriptions(path: &Path) -> Vec<String> {
+        let queue: Queue<Todo> = Queue::load_from(path).unwrap();
+        queue.iter().map(|todo| todo.description.clone()).collect()
+    }
+
+    #[test]
+    fn test_render_and_complete() {
+        let path = temp_path("complete");
+        let mut app = App::new(Queue::open(&path).unwrap());
+        add(&mut app, "write report");
+        add(&mut app, "buy milk");
+        press(&mut app, KeyCode::Up);
+
+        let screen = render(&app);
+        assert!(screen[0].starts_with("┌ todo: 2 pending ─"));
+        assert_eq!(screen[1], row("> [1] write report"));
+        assert_eq!(screen[2], row("  [2] buy milk"));
+        assert_eq!(screen[6], HELP);
+
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(render(&app)[5], "Completed [1] write report");
+        assert_eq!(descriptions(&path), vec!["buy milk"]);
+    }
+
+    #[test]
+    fn test_edit_reorder_and_filter() {
+        let path = temp_path("edit");
+        let mut app = App::new(Queue::open(&path).unwrap());
+        add(&mut app, "alpha");
+        add(&mut app, "beta");
+        add(&mut app, "gamma");
+
+        press(&mut app, KeyCode::Char('K'));
+        assert_eq!(descriptions(&path), vec!["alpha", "gamma", "beta"]);
+
+        press(&mut app, KeyCode::Char('e'));
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Char('A'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(descriptions(&path), vec!["alpha", "gammA", "beta"]);
+
+        press(&mut app, KeyCode::Char('/'));
+        press(&mut app, KeyCode::Char('t'));
+        let screen = render(&app);
+        assert!(screen[0].starts_with("┌ todo: 1 of 3 matching \"t\" ─"));
+        assert_eq!(screen[1], row("> [2] beta"));
+        assert_eq!(screen[5], "/t_");
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(render(&app)[3], row("  [2] beta"));
+    }
+
+    #[test]
+    fn test_reorder_with_filter() {
+        let path = temp_path("reorder-filter");
+        let mut app = App::new(Queue::open(&path).unwrap());
+        add(&mut app, "apple");
+        add(&mut app, "hidden");
+        add(&mut app, "avocado");
+        add(&mut app, "banana");
+
+        press(&mut app, KeyCode::Char('/'));
+        press(&mut app, KeyCode::Char('a'));
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Up);
+
+        // apple swaps with avocado; the hidden task stays second.
+        press(&mut app, KeyCode::Char('J'));
+        assert_eq!(
+            descriptions(&path),
+            vec!["avocado", "hidden", "apple", "banana"]
+        );
+        assert_eq!(render(&app)[2], row("> [1] apple"));
+
+        // And back up past it again.
+        press(&mut app, KeyCode::Char('K'));
+        assert_eq!(
+            descriptions(&path),
+            vec!["apple", "hidden", "avocado", "banana"]
+        );
+        assert_eq!(render(&app)[1], row("> [1] apple"));
+    }
+
+    #[test]
+    fn test_blocked_task_is_not_completed() {
+        let path = temp_path("blocked");
+        let mut queue: Queue<Todo> = Queue::open(&path).unwrap();
+        let first = commands::new_todo(&queue, "first".into(), None, &[]).unwrap();
+        queue.apply(Op::Enqueue(first));
+        let after = ["1".parse().unwrap()];
+        let second = commands::new_todo(&queue, "second".into(), None, &after).unwrap();
+        queue.apply(Op::Enqueue(second));
+
+        let mut app = App::new(queue);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(render(&app)[5], "blocked by 1");
+        assert_eq!(app.queue.len(), 2);
+    }
+}
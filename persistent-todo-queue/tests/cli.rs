@@ -0,0 +1,108 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use serde_json::Value;
+
+fn temp_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("todo-cli-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("todo.bin")
+}
+
+fn todo(file: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_persistent-todo-queue"))
+        .arg("--file")
+        .arg(file)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn json(bytes: &[u8]) -> Value {
+    serde_json::from_slice(bytes).unwrap()
+}
+
+/// Asserts that `output` failed with `code` and a JSON error of `kind`.
+fn assert_error(output: &Output, code: i32, kind: &str) {
+    assert_eq!(output.status.code(), Some(code));
+    assert!(output.stdout.is_empty());
+    assert_eq!(json(&output.stderr)["error"], kind);
+}
+
+#[test]
+fn test_json_output() {
+    let file = temp_path("output");
+
+    let output = todo(&file, &["--json", "add", "buy milk"]);
+    assert!(output.status.success());
+    let added = &json(&output.stdout)["added"];
+    assert_eq!(added["description"], "buy milk");
+    assert_eq!(added["id"]["counter"], 1);
+
+    let output = todo(&file, &["--json", "list"]);
+    assert!(output.status.success());
+    let list = json(&output.stdout);
+    assert_eq!(list["pending"][0]["description"], "buy milk");
+    assert_eq!(list["blocked"], Value::Array(Vec::new()));
+
+    let output = todo(&file, &["--json", "done"]);
+    assert!(output.status.success());
+    let done = json(&output.stdout);
+    assert_eq!(done["completed"]["description"], "buy milk");
+    assert!(done["completed"]["completed_at"].is_u64());
+    assert_eq!(done["recurred"], Value::Null);
+
+    // Without `--json` the same commands print text.
+    let output = todo(&file, &["list"]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "No tasks!\n");
+}
+
+#[test]
+fn test_exit_codes() {
+    let file = temp_path("exit-codes");
+
+    assert_error(&todo(&file, &["--json", "next"]), 3, "empty");
+    assert_error(&todo(&file, &["--json", "add", " "]), 2, "bad_args");
+    assert_error(&todo(&file, &["--json", "edit", "7", "x"]), 2, "bad_args");
+
+    // The snapshot path is a directory, so it cannot be read.
+    let dir = file.parent().unwrap();
+    assert_error(&todo(dir, &["--json", "list"]), 4, "storage");
+
+    let output = todo(&file, &["next"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Error: no tasks\n"
+    );
+}
+
+#[test]
+fn test_parse_errors_as_json() {
+    let file = temp_path("parse-errors");
+
+    for args in [
+        &["--json", "frobnicate"][..],
+        &["add", "--json"],
+        &["--json", "add", "x", "--every", "0h"],
+    ] {
+        let output = todo(&file, args);
+        assert_error(&output, 2, "bad_args");
+        let message = json(&output.stderr)["message"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(!message.is_empty() && !message.contains('\n'), "{message}");
+    }
+
+    // Plain parse errors keep clap's own text, and help is not an error.
+    let output = todo(&file, &["frobnicate"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .starts_with("error:")
+    );
+    assert!(todo(&file, &["--json", "--help"]).status.success());
+}
@@ -0,0 +1,269 @@
+use std::path::PathBuf;
+
+use persistent_todo_queue::journal::Op;
+use persistent_todo_queue::queue::{self, Queue};
+
+fn temp_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("todo-queue-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("queue.bin")
+}
+
+#[test]
+fn test_fifo_order() {
+    let mut queue = Queue::new();
+    queue.enqueue(1);
+    queue.enqueue(2);
+    assert_eq!(queue.dequeue(), Some(1));
+    queue.enqueue(3);
+    assert_eq!(queue.peek(), Some(&2));
+    assert_eq!(queue.iter().copied().collect::<Vec<_>>(), vec![2, 3]);
+    assert_eq!(queue.dequeue(), Some(2));
+    assert_eq!(queue.dequeue(), Some(3));
+    assert_eq!(queue.dequeue(), None);
+}
+
+#[test]
+fn test_iterator_traits() {
+    let mut queue: Queue<u32> = (1..=3).collect();
+    queue.dequeue();
+    queue.extend([4, 5]);
+
+    let borrowed: Vec<u32> = (&queue).into_iter().copied().collect();
+    assert_eq!(borrowed, vec![2, 3, 4, 5]);
+    assert_eq!(queue.iter().next_back(), Some(&5));
+
+    let owned: Vec<u32> = queue.into_iter().collect();
+    assert_eq!(owned, vec![2, 3, 4, 5]);
+}
+
+#[test]
+fn test_drain_and_retain() {
+    let mut queue: Queue<u32> = Queue::with_capacity(8);
+    queue.extend(1..=6);
+    queue.dequeue();
+
+    let mut seen = Vec::new();
+    queue.retain(|&n| {
+        seen.push(n);
+        n % 2 == 0
+    });
+    assert_eq!(seen, vec![2, 3, 4, 5, 6]);
+    assert_eq!(queue.iter().copied().collect::<Vec<_>>(), vec![2, 4, 6]);
+
+    assert_eq!(queue.drain().collect::<Vec<_>>(), vec![2, 4, 6]);
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn test_insert_and_move() {
+    let mut queue: Queue<u32> = (1..=3).collect();
+    queue.dequeue();
+    queue.enqueue(4);
+    queue.insert(0, 1);
+    queue.insert(2, 9);
+    assert_eq!(
+        queue.iter().copied().collect::<Vec<_>>(),
+        vec![1, 2, 9, 3, 4]
+    );
+
+    queue.move_item(4, 0);
+    queue.move_item(1, 3);
+    queue.move_item(7, 0);
+    assert_eq!(
+        queue.iter().copied().collect::<Vec<_>>(),
+        vec![4, 2, 9, 1, 3]
+    );
+}
+
+#[test]
+fn test_journal_replay() {
+    let path = temp_path("replay");
+
+    let mut queue: Queue<String> = Queue::open(&path).unwrap();
+    queue.record(Op::Enqueue("a".to_string())).unwrap();
+    queue.record(Op::Enqueue("b".to_string())).unwrap();
+    queue.record(Op::Dequeue).unwrap();
+    queue
+        .record(Op::Edit {
+            index: 0,
+            item: "B".to_string(),
+        })
+        .unwrap();
+    assert!(path.with_extension("journal").exists());
+
+    let reloaded: Queue<String> = Queue::open(&path).unwrap();
+    assert_eq!(reloaded.iter().collect::<Vec<_>>(), vec!["B"]);
+    assert_eq!(reloaded.next_id, 3);
+    assert_eq!(reloaded.origin, queue.origin);
+}
+
+#[test]
+fn test_save_compacts_journal() {
+    let path = temp_path("compact");
+
+    let mut queue: Queue<u64> = Queue::open(&path).unwrap();
+    queue.record(Op::Enqueue(7)).unwrap();
+    queue.save().unwrap();
+    assert!(!path.with_extension("journal").exists());
+
+    let reloaded: Queue<u64> = Queue::load_from(&path).unwrap();
+    assert_eq!(reloaded.iter().collect::<Vec<_>>(), vec![&7]);
+}
+
+#[test]
+fn test_record_without_backing_file() {
+    let mut queue: Queue<u64> = Queue::new();
+    assert!(queue.record(Op::Enqueue(1)).is_err());
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn test_replay_skips_ops_already_in_snapshot() {
+    let path = temp_path("stale-journal");
+    let journal = path.with_extension("journal");
+
+    let mut queue: Queue<String> = Queue::open(&path).unwrap();
+    queue.record(Op::Enqueue("a".to_string())).unwrap();
+    queue.record(Op::Enqueue("b".to_string())).unwrap();
+    queue.record(Op::Dequeue).unwrap();
+
+    // Crash after the snapshot was renamed into place but before the journal
+    // it supersedes was removed.
+    let stale = std::fs::read(&journal).unwrap();
+    queue.save().unwrap();
+    std::fs::write(&journal, stale).unwrap();
+
+    let mut reloaded: Queue<String> = Queue::open(&path).unwrap();
+    assert_eq!(reloaded.iter().collect::<Vec<_>>(), vec!["b"]);
+    assert_eq!(reloaded.next_id, 3);
+
+    // Records appended after the stale ones are still replayed.
+    reloaded.record(Op::Enqueue("c".to_string())).unwrap();
+    let reloaded: Queue<String> = Queue::open(&path).unwrap();
+    assert_eq!(reloaded.iter().collect::<Vec<_>>(), vec!["b", "c"]);
+}
+
+#[test]
+fn test_corrupt_journal_tail() {
+    let path = temp_path("corrupt");
+    let journal = path.with_extension("journal");
+
+    let mut queue: Queue<u64> = Queue::open(&path).unwrap();
+    queue.record(Op::Enqueue(1)).unwrap();
+    queue.record(Op::Enqueue(2)).unwrap();
+
+    // A complete record whose payload does not decode.
+    let mut bytes = std::fs::read(&journal).unwrap();
+    bytes.extend_from_slice(&12u32.to_le_bytes());
+    bytes.extend_from_slice(&[0xff; 12]);
+    std::fs::write(&journal, bytes).unwrap();
+
+    let mut reloaded: Queue<u64> = Queue::open(&path).unwrap();
+    assert_eq!(reloaded.iter().collect::<Vec<_>>(), vec![&1, &2]);
+
+    // The bad record was cut off, so a new one is not lost behind it.
+    reloaded.record(Op::Enqueue(3)).unwrap();
+    let reloaded: Queue<u64> = Queue::open(&path).unwrap();
+    assert_eq!(reloaded.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+}
+
+#[test]
+fn test_format_header() {
+    let path = temp_path("header");
+    let journal = path.with_extension("journal");
+
+    let mut queue: Queue<u64> = Queue::open(&path).unwrap();
+    queue.record(Op::Enqueue(1)).unwrap();
+    assert!(queue::is_versioned(&path).unwrap());
+    assert!(std::fs::read(&path).unwrap().starts_with(b"TDQS\x01"));
+    assert!(std::fs::read(&journal).unwrap().starts_with(b"TDQJ\x01"));
+
+    // A file from a newer version is refused rather than misread.
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[4] = 2;
+    std::fs::write(&path, bytes).unwrap();
+    assert!(Queue::<u64>::load_from(&path).is_err());
+
+    // So is a snapshot without a header, which needs migrating first.
+    std::fs::write(&path, borsh::to_vec(&(0u32, 0u32, 1u64)).unwrap()).unwrap();
+    assert!(!queue::is_versioned(&path).unwrap());
+    assert!(Queue::<u64>::load_from(&path).is_err());
+}
+
+#[test]
+fn test_complete_and_enqueue_is_one_record() {
+    let path = temp_path("reschedule");
+
+    let mut queue: Queue<String> = Queue::open(&path).unwrap();
+    queue
+        .record(Op::Enqueue("water plants".to_string()))
+        .unwrap();
+    let before = std::fs::metadata(path.with_extension("journal"))
+        .unwrap()
+        .len();
+    queue
+        .record(Op::CompleteAndEnqueue {
+            index: 0,
+            item: "watered plants".to_string(),
+            next: "water plants again".to_string(),
+        })
+        .unwrap();
+
+    // Cutting the record short drops the completion and the follow-up together.
+    let journal = path.with_extension("journal");
+    let bytes = std::fs::read(&journal).unwrap();
+    std::fs::write(&journal, &bytes[..bytes.len() - 1]).unwrap();
+    let torn: Queue<String> = Queue::open(&path).unwrap();
+    assert_eq!(torn.iter().collect::<Vec<_>>(), vec!["water plants"]);
+    assert!(torn.completed().is_empty());
+    assert_eq!(std::fs::metadata(&journal).unwrap().len(), before);
+
+    queue.save().unwrap();
+    let reloaded: Queue<String> = Queue::open(&path).unwrap();
+    assert_eq!(
+        reloaded.iter().collect::<Vec<_>>(),
+        vec!["water plants again"]
+    );
+    assert_eq!(reloaded.completed(), ["watered plants".to_string()]);
+    assert_eq!(reloaded.next_id, 3);
+}
+
+#[test]
+fn test_replay_remove_complete_and_move() {
+    let path = temp_path("ops");
+
+    let mut queue: Queue<u64> = Queue::open(&path).unwrap();
+    for n in 1..=5 {
+        queue.record(Op::Enqueue(n)).unwrap();
+    }
+    queue.record(Op::Remove { index: 1 }).unwrap();
+    queue.record(Op::Complete { index: 0, item: 10 }).unwrap();
+    queue.record(Op::Move { from: 2, to: 0 }).unwrap();
+    // Out of range: recorded, but changes nothing on replay either.
+    queue.record(Op::Remove { index: 9 }).unwrap();
+    queue.record(Op::Complete { index: 9, item: 90 }).unwrap();
+
+    let reloaded: Queue<u64> = Queue::open(&path).unwrap();
+    assert_eq!(reloaded.iter().collect::<Vec<_>>(), vec![&5, &3, &4]);
+    assert_eq!(reloaded.completed(), [10]);
+}
+
+#[test]
+fn test_compact_completed_keeps_every_item() {
+    let mut queue: Queue<usize> = Queue::new();
+    for n in 0..1005 {
+        queue.enqueue(n);
+        queue.apply(Op::Complete { index: 0, item: n });
+    }
+    queue.compact_completed(1000, |n| *n = 0);
+    assert_eq!(queue.completed().len(), 1005);
+    assert!(queue.completed()[..5].iter().all(|&n| n == 0));
+    assert_eq!(queue.completed()[5], 5);
+    assert_eq!(queue.completed().last(), Some(&1004));
+
+    // Keeping more than there are leaves everything alone.
+    queue.compact_completed(2000, |n| *n = 7);
+    assert_eq!(queue.completed()[5], 5);
+}
@@ -0,0 +1,399 @@
+use solana_instruction::{AccountMeta, Instruction};
+use solana_pubkey::Pubkey;
+
+use crate::{
+    config_address, escrow_address, program_data_address, vault_address, Variant,
+    ASSOCIATED_TOKEN_PROGRAM_ID, ID, NATIVE_MINT,
+};
+
+/// Discriminator pairs `(unsafe, wincode)`, as dispatched by the entrypoint.
+const MAKE: (u8, u8) = (0, 3);
+const TAKE: (u8, u8) = (1, 4);
+const CANCEL: (u8, u8) = (2, 5);
+const TAKE_PARTIAL: (u8, u8) = (6, 7);
+const RECLAIM: (u8, u8) = (8, 9);
+const AMEND: (u8, u8) = (10, 11);
+const SET_CONFIG: (u8, u8) = (12, 13);
+const TAKE_MANY: (u8, u8) = (14, 15);
+const MIGRATE: (u8, u8) = (16, 17);
+
+fn discriminator(variant: Variant, (r#unsafe, wincode): (u8, u8)) -> u8 {
+    match variant {
+        Variant::Unsafe => r#unsafe,
+        Variant::Wincode => wincode,
+    }
+}
+
+/// Opens an offer of `amount_to_give` of `mint_a` for `amount_to_receive` of
+/// `mint_b`, moving the deposit into a vault owned by the escrow PDA.
+#[derive(Clone, Copy, Debug)]
+pub struct Make {
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    /// Maker's `mint_a` account the deposit is paid from.
+    pub maker_ata_a: Pubkey,
+    /// Token program that owns `mint_a`.
+    pub token_program: Pubkey,
+    pub seed: u64,
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+    /// Seconds from `make` until the offer expires.
+    pub duration: i64,
+    /// Restricts the offer to one taker; `None` lets anyone take it.
+    pub taker: Option<Pubkey>,
+}
+
+impl Make {
+    pub fn instruction(&self, variant: Variant) -> Instruction {
+        let (escrow, bump) = escrow_address(&self.maker, self.seed);
+        let vault = vault_address(&escrow, &self.mint_a, &self.token_program);
+
+        // [seed:8][amount_to_receive:8][amount_to_give:8][duration:8][taker:32][bump:1]
+        let mut data = vec![discriminator(variant, MAKE)];
+        data.extend_from_slice(&self.seed.to_le_bytes());
+        data.extend_from_slice(&self.amount_to_receive.to_le_bytes());
+        data.extend_from_slice(&self.amount_to_give.to_le_bytes());
+        data.extend_from_slice(&self.duration.to_le_bytes());
+        data.extend_from_slice(self.taker.unwrap_or_default().as_ref());
+        data.push(bump);
+
+        Instruction {
+            program_id: ID,
+            accounts: vec![
+                AccountMeta::new(self.maker, true),
+                AccountMeta::new_readonly(self.mint_a, false),
+                AccountMeta::new_readonly(self.mint_b, false),
+                AccountMeta::new(escrow, false),
+                AccountMeta::new(self.maker_ata_a, false),
+                AccountMeta::new(vault, false),
+                AccountMeta::new_readonly(solana_sdk_ids::system_program::ID, false),
+                AccountMeta::new_readonly(self.token_program, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+            ],
+            data,
+        }
+    }
+}
+
+/// Fills `maker`'s whole offer with `seed`: the taker pays `mint_b` to the
+/// maker and receives the vault's `mint_a`, and the escrow is closed.
+#[derive(Clone, Copy, Debug)]
+pub struct Take {
+    pub taker: Pubkey,
+    pub maker: Pubkey,
+    pub seed: u64,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    /// Taker's `mint_a` account, credited from the vault.
+    pub taker_ata_a: Pubkey,
+    /// Taker's `mint_b` account the payment comes from.
+    pub taker_ata_b: Pubkey,
+    /// Maker's `mint_b` account the payment goes to.
+    pub maker_ata_b: Pubkey,
+    /// Token programs that own `mint_a` and `mint_b`.
+    pub token_program_a: Pubkey,
+    pub token_program_b: Pubkey,
+    /// `mint_b` account of the config's fee recipient. Only checked while a
+    /// fee is configured.
+    pub fee_ata_b: Pubkey,
+}
+
+impl Take {
+    pub fn instruction(&self, variant: Variant) -> Instruction {
+        Instruction {
+            program_id: ID,
+            accounts: self.accounts(),
+            data: vec![discriminator(variant, TAKE)],
+        }
+    }
+
+    fn accounts(&self) -> Vec<AccountMeta> {
+        let (escrow, _) = escrow_address(&self.maker, self.seed);
+        let vault = vault_address(&escrow, &self.mint_a, &self.token_program_a);
+
+        vec![
+            AccountMeta::new(self.taker, true),
+            AccountMeta::new(self.maker, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(self.taker_ata_a, false),
+            AccountMeta::new(self.taker_ata_b, false),
+            AccountMeta::new(self.maker_ata_b, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(self.token_program_a, false),
+            mint_a_meta(&self.mint_a),
+            AccountMeta::new_readonly(self.mint_b, false),
+            AccountMeta::new_readonly(self.token_program_b, false),
+            AccountMeta::new_readonly(config_address().0, false),
+            AccountMeta::new(self.fee_ata_b, false),
+        ]
+    }
+}
+
+/// Pays `fill` of what is left of the offer and receives the pro-rata share of
+/// the vault. Takes the same accounts as [`Take`].
+#[derive(Clone, Copy, Debug)]
+pub struct TakePartial {
+    pub take: Take,
+    pub fill: u64,
+}
+
+impl TakePartial {
+    pub fn instruction(&self, variant: Variant) -> Instruction {
+        let mut data = vec![discriminator(variant, TAKE_PARTIAL)];
+        data.extend_from_slice(&self.fill.to_le_bytes());
+
+        Instruction {
+            program_id: ID,
+            accounts: self.take.accounts(),
+            data,
+        }
+    }
+}
+
+/// One of the offers swept by [`TakeMany`].
+#[derive(Clone, Copy, Debug)]
+pub struct TakeManyOffer {
+    pub maker: Pubkey,
+    pub seed: u64,
+    /// Maker's `mint_b` account the payment goes to.
+    pub maker_ata_b: Pubkey,
+}
+
+/// Fills several whole offers for the same pair in one instruction, as if each
+/// were sent to [`Take`]. If one fails, none are taken.
+#[derive(Clone, Debug)]
+pub struct TakeMany {
+    pub taker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub taker_ata_a: Pubkey,
+    pub taker_ata_b: Pubkey,
+    pub token_program_a: Pubkey,
+    pub token_program_b: Pubkey,
+    pub fee_ata_b: Pubkey,
+    /// At most 255.
+    pub offers: Vec<TakeManyOffer>,
+}
+
+impl TakeMany {
+    pub fn instruction(&self, variant: Variant) -> Instruction {
+        // [count:1]
+        let data = vec![discriminator(variant, TAKE_MANY), self.offers.len() as u8];
+
+        let mut accounts = vec![
+            AccountMeta::new(self.taker, true),
+            AccountMeta::new(self.taker_ata_a, false),
+            AccountMeta::new(self.taker_ata_b, false),
+            AccountMeta::new_readonly(self.token_program_a, false),
+            mint_a_meta(&self.mint_a),
+            AccountMeta::new_readonly(self.mint_b, false),
+            AccountMeta::new_readonly(self.token_program_b, false),
+            AccountMeta::new_readonly(config_address().0, false),
+            AccountMeta::new(self.fee_ata_b, false),
+        ];
+        for offer in &self.offers {
+            let (escrow, _) = escrow_address(&offer.maker, offer.seed);
+            accounts.extend([
+                AccountMeta::new(offer.maker, false),
+                AccountMeta::new(escrow, false),
+                AccountMeta::new(offer.maker_ata_b, false),
+                AccountMeta::new(
+                    vault_address(&escrow, &self.mint_a, &self.token_program_a),
+                    false,
+                ),
+            ]);
+        }
+
+        Instruction {
+            program_id: ID,
+            accounts,
+            data,
+        }
+    }
+}
+
+/// Closes `maker`'s offer with `seed`, returning the deposit to the maker.
+#[derive(Clone, Copy, Debug)]
+pub struct Cancel {
+    pub maker: Pubkey,
+    pub seed: u64,
+    pub mint_a: Pubkey,
+    /// Maker's `mint_a` account the deposit is returned to.
+    pub maker_ata_a: Pubkey,
+    /// Token program that owns `mint_a`.
+    pub token_program: Pubkey,
+}
+
+impl Cancel {
+    pub fn instruction(&self, variant: Variant) -> Instruction {
+        Instruction {
+            program_id: ID,
+            accounts: maker_vault_accounts(
+                &self.maker,
+                true,
+                self.seed,
+                &self.mint_a,
+                &self.maker_ata_a,
+                &self.token_program,
+            ),
+            data: vec![discriminator(variant, CANCEL)],
+        }
+    }
+}
+
+/// Like [`Cancel`], but for an expired offer and callable by anyone; the maker
+/// does not sign.
+#[derive(Clone, Copy, Debug)]
+pub struct Reclaim {
+    pub maker: Pubkey,
+    pub seed: u64,
+    pub mint_a: Pubkey,
+    pub maker_ata_a: Pubkey,
+    pub token_program: Pubkey,
+}
+
+impl Reclaim {
+    pub fn instruction(&self, variant: Variant) -> Instruction {
+        Instruction {
+            program_id: ID,
+            accounts: maker_vault_accounts(
+                &self.maker,
+                false,
+                self.seed,
+                &self.mint_a,
+                &self.maker_ata_a,
+                &self.token_program,
+            ),
+            data: vec![discriminator(variant, RECLAIM)],
+        }
+    }
+}
+
+/// Reprices `maker`'s open offer with `seed` and resizes its deposit, moving
+/// the difference between the vault and `maker_ata_a`.
+#[derive(Clone, Copy, Debug)]
+pub struct Amend {
+    pub maker: Pubkey,
+    pub seed: u64,
+    pub mint_a: Pubkey,
+    pub maker_ata_a: Pubkey,
+    pub token_program: Pubkey,
+    /// New price, in `mint_b`.
+    pub amount_to_receive: u64,
+    /// New deposit, in `mint_a`.
+    pub amount_to_give: u64,
+}
+
+impl Amend {
+    pub fn instruction(&self, variant: Variant) -> Instruction {
+        // [amount_to_receive:8][amount_to_give:8]
+        let mut data = vec![discriminator(variant, AMEND)];
+        data.extend_from_slice(&self.amount_to_receive.to_le_bytes());
+        data.extend_from_slice(&self.amount_to_give.to_le_bytes());
+
+        Instruction {
+            program_id: ID,
+            accounts: maker_vault_accounts(
+                &self.maker,
+                true,
+                self.seed,
+                &self.mint_a,
+                &self.maker_ata_a,
+                &self.token_program,
+            ),
+            data,
+        }
+    }
+}
+
+/// Creates the fee config, or updates it when signed by its admin.
+#[derive(Clone, Copy, Debug)]
+pub struct SetConfig {
+    /// Must be the program's upgrade authority, who pays for the config, on
+    /// creation and the stored admin afterwards.
+    pub authority: Pubkey,
+    pub admin: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub fee_basis_points: u16,
+}
+
+impl SetConfig {
+    pub fn instruction(&self, variant: Variant) -> Instruction {
+        // [admin:32][fee_recipient:32][fee_basis_points:2]
+        let mut data = vec![discriminator(variant, SET_CONFIG)];
+        data.extend_from_slice(self.admin.as_ref());
+        data.extend_from_slice(self.fee_recipient.as_ref());
+        data.extend_from_slice(&self.fee_basis_points.to_le_bytes());
+
+        Instruction {
+            program_id: ID,
+            accounts: vec![
+                AccountMeta::new(self.authority, true),
+                AccountMeta::new(config_address().0, false),
+                AccountMeta::new_readonly(solana_sdk_ids::system_program::ID, false),
+                AccountMeta::new_readonly(program_data_address(), false),
+            ],
+            data,
+        }
+    }
+}
+
+/// Rewrites `maker`'s offer with `seed`, opened before offers could name a
+/// taker, in the current layout so the other instructions accept it again.
+#[derive(Clone, Copy, Debug)]
+pub struct Migrate {
+    /// Pays the rent of the account's extra space.
+    pub payer: Pubkey,
+    pub maker: Pubkey,
+    pub seed: u64,
+}
+
+impl Migrate {
+    pub fn instruction(&self, variant: Variant) -> Instruction {
+        let (escrow, _) = escrow_address(&self.maker, self.seed);
+
+        Instruction {
+            program_id: ID,
+            accounts: vec![
+                AccountMeta::new(self.payer, true),
+                AccountMeta::new(escrow, false),
+                AccountMeta::new_readonly(solana_sdk_ids::system_program::ID, false),
+            ],
+            data: vec![discriminator(variant, MIGRATE)],
+        }
+    }
+}
+
+/// Accounts of the instructions that move the vault's tokens back to the maker:
+/// `cancel`, `reclaim` and `amend`.
+fn maker_vault_accounts(
+    maker: &Pubkey,
+    maker_signs: bool,
+    seed: u64,
+    mint_a: &Pubkey,
+    maker_ata_a: &Pubkey,
+    token_program: &Pubkey,
+) -> Vec<AccountMeta> {
+    let (escrow, _) = escrow_address(maker, seed);
+    let vault = vault_address(&escrow, mint_a, token_program);
+
+    vec![
+        AccountMeta::new(*maker, maker_signs),
+        AccountMeta::new(escrow, false),
+        AccountMeta::new(*maker_ata_a, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(*token_program, false),
+        mint_a_meta(mint_a),
+    ]
+}
+
+/// Writable, since closing a Token-2022 vault harvests withheld fees to the
+/// mint, unless `mint_a` is the system program standing in for native SOL.
+fn mint_a_meta(mint_a: &Pubkey) -> AccountMeta {
+    if *mint_a == NATIVE_MINT {
+        AccountMeta::new_readonly(*mint_a, false)
+    } else {
+        AccountMeta::new(*mint_a, false)
+    }
+}
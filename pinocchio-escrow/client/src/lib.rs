@@ -0,0 +1,85 @@
+//! Off-chain client for the pinocchio escrow program: instruction builders,
+//! address derivation and a decoder for the `Escrow` account.
+//!
+//! Every instruction exists twice on chain, once parsing its data with raw
+//! pointer reads and once with wincode. Both take the same accounts and data,
+//! so builders only need to know which [`Variant`] to address.
+
+mod instructions;
+mod state;
+
+pub use instructions::{
+    Amend, Cancel, Make, Migrate, Reclaim, SetConfig, Take, TakeMany, TakeManyOffer, TakePartial,
+};
+pub use state::{Config, DecodeError, Escrow};
+
+use solana_pubkey::Pubkey;
+
+pub const ID: Pubkey = Pubkey::from_str_const("4ibrEMW5F6hKnkW4jVedswYv6H6VtwPN6ar6dvXDN1nT");
+
+pub const TOKEN_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// Pass as `mint_a` or `mint_b`, and as that leg's token program, to trade
+/// native SOL. The leg then has no token accounts: pass the wallets (see
+/// [`token_account_address`]) and the escrow holds the deposit itself.
+pub const NATIVE_MINT: Pubkey = solana_sdk_ids::system_program::ID;
+
+/// Which implementation of an instruction to call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variant {
+    /// Instruction data read in place with unaligned pointer loads.
+    Unsafe,
+    /// Instruction data deserialized with wincode.
+    Wincode,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 2] = [Variant::Unsafe, Variant::Wincode];
+}
+
+/// Escrow PDA of `maker`'s offer with `seed`, and its bump.
+pub fn escrow_address(maker: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &seed.to_le_bytes()], &ID)
+}
+
+/// The program's single fee config PDA and its bump.
+pub fn config_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"config"], &ID)
+}
+
+/// The program's `ProgramData` account under the upgradeable loader, which
+/// records its upgrade authority. `SetConfig` passes it so the program can
+/// check who may create the config.
+pub fn program_data_address() -> Pubkey {
+    Pubkey::find_program_address(&[ID.as_ref()], &solana_sdk_ids::bpf_loader_upgradeable::ID).0
+}
+
+/// Associated token account of `owner` for `mint` under `token_program`.
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Account that holds `owner`'s `mint` in an escrow instruction: its
+/// associated token account, or for [`NATIVE_MINT`] the wallet itself.
+pub fn token_account_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    if *mint == NATIVE_MINT {
+        *owner
+    } else {
+        associated_token_address(owner, mint, token_program)
+    }
+}
+
+/// Vault holding the `mint_a` deposit of `escrow`: the escrow's associated
+/// token account, created by `make`, or the escrow itself for native SOL.
+pub fn vault_address(escrow: &Pubkey, mint_a: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    token_account_address(escrow, mint_a, token_program)
+}
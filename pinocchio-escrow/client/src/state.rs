@@ -0,0 +1,139 @@
+use core::fmt;
+
+use solana_pubkey::Pubkey;
+
+/// Decoded escrow account.
+///
+/// On chain the account is 168 bytes:
+/// `[discriminator:1][version:1][bump:1][padding:5][maker:32][mint_a:32]`
+/// `[mint_b:32][taker:32][seed:8][amount_to_receive:8][amount_to_give:8]`
+/// `[expires_at:8]`, integers little-endian.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Escrow {
+    pub bump: u8,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    /// The only key allowed to take the offer, or `None` if anyone may.
+    pub taker: Option<Pubkey>,
+    pub seed: u64,
+    /// What is still owed to the maker, in `mint_b`.
+    pub amount_to_receive: u64,
+    /// What is still left in the vault, in `mint_a`.
+    pub amount_to_give: u64,
+    /// Unix timestamp from which the offer can no longer be taken.
+    pub expires_at: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The account is not the length of the type being decoded.
+    InvalidLength(usize),
+    /// The first byte is not the discriminator of the type being decoded.
+    InvalidDiscriminator(u8),
+    /// The layout version is one this client does not know.
+    UnsupportedVersion(u8),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidLength(len) => write!(f, "unexpected account length {len}"),
+            DecodeError::InvalidDiscriminator(byte) => {
+                write!(f, "unexpected account discriminator {byte}")
+            }
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported account layout version {version}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl Escrow {
+    pub const LEN: usize = 168;
+    pub const DISCRIMINATOR: u8 = 1;
+    pub const VERSION: u8 = 2;
+
+    /// Decodes the data of an escrow account, checking its header the same
+    /// way the program does.
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        check_header(data, Self::LEN, Self::DISCRIMINATOR, Self::VERSION)?;
+
+        let bytes = |at: usize| -> [u8; 8] { data[at..at + 8].try_into().unwrap() };
+        let taker = pubkey_at(data, 104);
+        Ok(Escrow {
+            bump: data[2],
+            maker: pubkey_at(data, 8),
+            mint_a: pubkey_at(data, 40),
+            mint_b: pubkey_at(data, 72),
+            taker: (taker != Pubkey::default()).then_some(taker),
+            seed: u64::from_le_bytes(bytes(136)),
+            amount_to_receive: u64::from_le_bytes(bytes(144)),
+            amount_to_give: u64::from_le_bytes(bytes(152)),
+            expires_at: i64::from_le_bytes(bytes(160)),
+        })
+    }
+}
+
+/// Decoded fee config.
+///
+/// On chain the account is 72 bytes:
+/// `[discriminator:1][version:1][bump:1][padding:3][fee_basis_points:2]`
+/// `[admin:32][fee_recipient:32]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    pub bump: u8,
+    /// Share of every take's payment routed to `fee_recipient`.
+    pub fee_basis_points: u16,
+    pub admin: Pubkey,
+    /// Wallet whose `mint_b` token accounts receive the fee.
+    pub fee_recipient: Pubkey,
+}
+
+impl Config {
+    pub const LEN: usize = 72;
+    pub const DISCRIMINATOR: u8 = 2;
+    pub const VERSION: u8 = 1;
+    /// Highest `fee_basis_points` the program accepts, 10%.
+    pub const MAX_FEE_BASIS_POINTS: u16 = 1_000;
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        check_header(data, Self::LEN, Self::DISCRIMINATOR, Self::VERSION)?;
+
+        Ok(Config {
+            bump: data[2],
+            fee_basis_points: u16::from_le_bytes([data[6], data[7]]),
+            admin: pubkey_at(data, 8),
+            fee_recipient: pubkey_at(data, 40),
+        })
+    }
+
+    /// Fee the program takes from a payment of `amount`, rounded down.
+    pub fn fee_for(&self, amount: u64) -> u64 {
+        (amount as u128 * self.fee_basis_points as u128 / 10_000) as u64
+    }
+}
+
+fn check_header(
+    data: &[u8],
+    len: usize,
+    discriminator: u8,
+    version: u8,
+) -> Result<(), DecodeError> {
+    if data.len() != len {
+        return Err(DecodeError::InvalidLength(data.len()));
+    }
+    if data[0] != discriminator {
+        return Err(DecodeError::InvalidDiscriminator(data[0]));
+    }
+    if data[1] != version {
+        return Err(DecodeError::UnsupportedVersion(data[1]));
+    }
+    Ok(())
+}
+
+fn pubkey_at(data: &[u8], at: usize) -> Pubkey {
+    Pubkey::new_from_array(data[at..at + 32].try_into().unwrap())
+}
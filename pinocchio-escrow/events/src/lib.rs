@@ -0,0 +1,222 @@
+//! Decoder for the events the pinocchio escrow program logs, so indexers can
+//! follow offers from transaction logs instead of diffing accounts.
+//!
+//! The program logs each event as one `sol_log_data` record, which appears in
+//! the logs as `Program data: <base64>`: a discriminator byte, then the fields
+//! in order, addresses as 32 bytes and amounts as little-endian `u64`.
+//! [`parse_logs`] picks out the records logged by the escrow itself, also when
+//! it is called through CPI, and [`Event::decode`] reads a single record.
+
+use core::fmt;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_pubkey::Pubkey;
+
+// The program's own record layout, so both sides agree on it.
+#[path = "../../src/events/layout.rs"]
+mod layout;
+
+use layout::{AMENDED, AMENDED_LEN, CANCELLED, CANCELLED_LEN, MADE, MADE_LEN, TAKEN, TAKEN_LEN};
+
+/// An event logged by the escrow program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Made(Made),
+    Taken(Taken),
+    Cancelled(Cancelled),
+    Amended(Amended),
+}
+
+/// Logged by `make` once the deposit is in the vault.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Made {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+}
+
+/// Logged by `take` and by every `take_partial` fill, and so once per offer
+/// by `take_many`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Taken {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    /// What the taker paid in `mint_b`: the offer's price, which includes
+    /// `fee`. Token-2022 transfer fees the taker pays on top are not counted.
+    pub amount_paid: u64,
+    /// What left the vault for the taker in `mint_a`, before any Token-2022
+    /// transfer fee is withheld from it.
+    pub amount_received: u64,
+    /// The part of `amount_paid` that went to the fee recipient.
+    pub fee: u64,
+}
+
+/// Logged by `cancel`, and by `reclaim` once the offer has expired.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    /// What went back to the maker in `mint_a`.
+    pub amount_returned: u64,
+}
+
+/// Logged by `amend` with the offer's new terms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Amended {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub amount_to_receive: u64,
+    /// What the vault holds in `mint_a` after the top-up or refund.
+    pub amount_to_give: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventError {
+    /// A `Program data:` entry is not valid base64.
+    InvalidBase64,
+    /// The record is not the length of its event.
+    InvalidLength(usize),
+    /// The first byte is not the discriminator of any event.
+    UnknownDiscriminator(u8),
+}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventError::InvalidBase64 => write!(f, "event data is not valid base64"),
+            EventError::InvalidLength(len) => write!(f, "unexpected event length {len}"),
+            EventError::UnknownDiscriminator(byte) => {
+                write!(f, "unknown event discriminator {byte}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EventError {}
+
+impl Event {
+    /// Decodes one record, as logged by the program.
+    pub fn decode(data: &[u8]) -> Result<Self, EventError> {
+        let Some((&discriminator, fields)) = data.split_first() else {
+            return Err(EventError::InvalidLength(0));
+        };
+
+        let len = match discriminator {
+            MADE => MADE_LEN,
+            TAKEN => TAKEN_LEN,
+            CANCELLED => CANCELLED_LEN,
+            AMENDED => AMENDED_LEN,
+            other => return Err(EventError::UnknownDiscriminator(other)),
+        };
+        if data.len() != len {
+            return Err(EventError::InvalidLength(data.len()));
+        }
+
+        let mut fields = Fields(fields);
+        let event = match discriminator {
+            MADE => Event::Made(Made {
+                escrow: fields.pubkey(),
+                maker: fields.pubkey(),
+                mint_a: fields.pubkey(),
+                mint_b: fields.pubkey(),
+                amount_to_receive: fields.amount(),
+                amount_to_give: fields.amount(),
+            }),
+            TAKEN => Event::Taken(Taken {
+                escrow: fields.pubkey(),
+                maker: fields.pubkey(),
+                taker: fields.pubkey(),
+                mint_a: fields.pubkey(),
+                mint_b: fields.pubkey(),
+                amount_paid: fields.amount(),
+                amount_received: fields.amount(),
+                fee: fields.amount(),
+            }),
+            CANCELLED => Event::Cancelled(Cancelled {
+                escrow: fields.pubkey(),
+                maker: fields.pubkey(),
+                mint_a: fields.pubkey(),
+                amount_returned: fields.amount(),
+            }),
+            _ => Event::Amended(Amended {
+                escrow: fields.pubkey(),
+                maker: fields.pubkey(),
+                mint_a: fields.pubkey(),
+                amount_to_receive: fields.amount(),
+                amount_to_give: fields.amount(),
+            }),
+        };
+        Ok(event)
+    }
+}
+
+/// Events logged by the escrow program in a transaction's log messages, in
+/// order.
+///
+/// Follows the `invoke`, `success` and `failed` lines to tell which program
+/// logged each `Program data:` entry, so data logged by other programs in the
+/// same transaction is skipped.
+pub fn parse_logs<S: AsRef<str>>(logs: &[S]) -> Result<Vec<Event>, EventError> {
+    let escrow_id = escrow_client::ID.to_string();
+    let mut invoked: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+
+    for line in logs {
+        let line = line.as_ref();
+
+        if let Some(entries) = line.strip_prefix("Program data: ") {
+            if invoked.last() != Some(&escrow_id.as_str()) {
+                continue;
+            }
+            let mut data = Vec::new();
+            for entry in entries.split_whitespace() {
+                let bytes = STANDARD
+                    .decode(entry)
+                    .map_err(|_| EventError::InvalidBase64)?;
+                data.extend_from_slice(&bytes);
+            }
+            events.push(Event::decode(&data)?);
+            continue;
+        }
+
+        let Some((program, message)) = line
+            .strip_prefix("Program ")
+            .and_then(|rest| rest.split_once(' '))
+        else {
+            continue;
+        };
+        if message.starts_with("invoke [") {
+            invoked.push(program);
+        } else if message == "success" || message.starts_with("failed") {
+            invoked.pop();
+        }
+    }
+
+    Ok(events)
+}
+
+/// The fields of a record after its discriminator, already checked to be long
+/// enough.
+struct Fields<'a>(&'a [u8]);
+
+impl Fields<'_> {
+    fn pubkey(&mut self) -> Pubkey {
+        let (bytes, rest) = self.0.split_at(32);
+        self.0 = rest;
+        Pubkey::new_from_array(bytes.try_into().unwrap())
+    }
+
+    fn amount(&mut self) -> u64 {
+        let (bytes, rest) = self.0.split_at(8);
+        self.0 = rest;
+        u64::from_le_bytes(bytes.try_into().unwrap())
+    }
+}
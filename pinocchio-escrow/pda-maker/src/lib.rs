@@ -0,0 +1,108 @@
+//! A program whose PDA at `["authority"]` makes and takes escrow offers
+//! through `escrow::cpi`, used by the escrow's `cpi_test`.
+//!
+//! Each instruction takes the escrow instruction's accounts in the same order,
+//! with the escrow program appended, and the escrow instruction's data with the
+//! authority's bump in front.
+
+#![allow(unexpected_cfgs)]
+
+use escrow::cpi::{Make, Take};
+use pinocchio::{
+    address::declare_id,
+    cpi::{Seed, Signer},
+    default_panic_handler,
+    error::ProgramError,
+    no_allocator, program_entrypoint, AccountView, Address, ProgramResult,
+};
+
+program_entrypoint!(process_instruction);
+no_allocator!();
+default_panic_handler!();
+
+declare_id!("EpT4bLcdy1jKbUvGv5HqP9bXmLiyki9n3JrtV1pSJMLG");
+
+pub const AUTHORITY_SEED: &[u8] = b"authority";
+
+fn process_instruction(
+    program_id: &Address,
+    accounts: &[AccountView],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if program_id != &ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let Some((&discriminator, [bump, data @ ..])) = instruction_data.split_first() else {
+        return Err(ProgramError::InvalidInstructionData);
+    };
+
+    // The runtime only lets the authority sign if these seeds derive it.
+    let bump_seed = [*bump];
+    let signer_seeds = [Seed::from(AUTHORITY_SEED), Seed::from(&bump_seed)];
+    let signer = Signer::from(&signer_seeds[..]);
+
+    match discriminator {
+        0 => process_make(accounts, data, signer),
+        1 => process_take(accounts, signer),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn process_make(accounts: &[AccountView], data: &[u8], signer: Signer) -> ProgramResult {
+    let [authority, mint_a, mint_b, escrow, authority_ata_a, vault, system_program, token_program, associated_token_program, _remaining @ ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if data.len() < 65 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let taker = Address::new_from_array(data[32..64].try_into().unwrap());
+
+    Make {
+        maker: authority,
+        mint_a,
+        mint_b,
+        escrow,
+        maker_ata_a: authority_ata_a,
+        vault,
+        system_program,
+        token_program,
+        associated_token_program,
+        seed: u64::from_le_bytes(data[0..8].try_into().unwrap()),
+        amount_to_receive: u64::from_le_bytes(data[8..16].try_into().unwrap()),
+        amount_to_give: u64::from_le_bytes(data[16..24].try_into().unwrap()),
+        duration: i64::from_le_bytes(data[24..32].try_into().unwrap()),
+        taker: (taker.as_array() != &[0; 32]).then_some(&taker),
+        bump: data[64],
+    }
+    .invoke_signed(&[signer])
+}
+
+fn process_take(accounts: &[AccountView], signer: Signer) -> ProgramResult {
+    let [authority, maker, escrow, authority_ata_a, authority_ata_b, maker_ata_b, vault, token_program_a, mint_a, mint_b, token_program_b, config, fee_ata_b, _remaining @ ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    Take {
+        taker: authority,
+        maker,
+        escrow,
+        taker_ata_a: authority_ata_a,
+        taker_ata_b: authority_ata_b,
+        maker_ata_b,
+        vault,
+        token_program_a,
+        mint_a,
+        mint_b,
+        token_program_b,
+        config,
+        fee_ata_b,
+    }
+    .invoke_signed(&[signer])
+}
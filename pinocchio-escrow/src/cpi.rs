@@ -0,0 +1,268 @@
+//! Invoke helpers for programs that open and take offers through CPI, enabled
+//! by the `cpi` feature.
+//!
+//! The escrow only asks that the maker or taker signs, so a caller program can
+//! act through one of its PDAs by passing the PDA's seeds to `invoke_signed`.
+//! Each struct mirrors the `escrow-client` builder of the same name, taking
+//! the accounts instead of deriving them: the caller passes the escrow PDA
+//! and vault, and the escrow checks both.
+//!
+//! Instructions are sent to the `unsafe` handlers, which behave the same as
+//! the `wincode` ones for less compute.
+
+use pinocchio::{
+    cpi::{invoke_signed, Signer},
+    instruction::{InstructionAccount, InstructionView},
+    AccountView, Address, ProgramResult,
+};
+
+use crate::entrypoint::ID;
+use crate::token;
+
+const MAKE: u8 = 0;
+const TAKE: u8 = 1;
+const CANCEL: u8 = 2;
+const TAKE_PARTIAL: u8 = 6;
+
+/// Opens an offer of `amount_to_give` of `mint_a` for `amount_to_receive` of
+/// `mint_b`. `maker` pays for the escrow and vault and must sign.
+pub struct Make<'a> {
+    pub maker: &'a AccountView,
+    pub mint_a: &'a AccountView,
+    pub mint_b: &'a AccountView,
+    /// Escrow PDA at `["escrow", maker, seed]`, created by the instruction.
+    pub escrow: &'a AccountView,
+    /// Maker's `mint_a` account the deposit is paid from.
+    pub maker_ata_a: &'a AccountView,
+    /// Escrow's associated `mint_a` account, or the escrow itself for SOL.
+    pub vault: &'a AccountView,
+    pub system_program: &'a AccountView,
+    /// Token program that owns `mint_a`.
+    pub token_program: &'a AccountView,
+    pub associated_token_program: &'a AccountView,
+    pub seed: u64,
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+    /// Seconds from `make` until the offer expires.
+    pub duration: i64,
+    /// Restricts the offer to one taker; `None` lets anyone take it.
+    pub taker: Option<&'a Address>,
+    /// Bump of `escrow`.
+    pub bump: u8,
+}
+
+impl Make<'_> {
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let instruction_accounts = [
+            InstructionAccount::writable_signer(self.maker.address()),
+            InstructionAccount::readonly(self.mint_a.address()),
+            InstructionAccount::readonly(self.mint_b.address()),
+            InstructionAccount::writable(self.escrow.address()),
+            InstructionAccount::writable(self.maker_ata_a.address()),
+            InstructionAccount::writable(self.vault.address()),
+            InstructionAccount::readonly(self.system_program.address()),
+            InstructionAccount::readonly(self.token_program.address()),
+            InstructionAccount::readonly(self.associated_token_program.address()),
+        ];
+
+        // [discriminator:1][seed:8][amount_to_receive:8][amount_to_give:8]
+        // [duration:8][taker:32][bump:1]
+        let mut data = [0u8; 66];
+        data[0] = MAKE;
+        data[1..9].copy_from_slice(&self.seed.to_le_bytes());
+        data[9..17].copy_from_slice(&self.amount_to_receive.to_le_bytes());
+        data[17..25].copy_from_slice(&self.amount_to_give.to_le_bytes());
+        data[25..33].copy_from_slice(&self.duration.to_le_bytes());
+        if let Some(taker) = self.taker {
+            data[33..65].copy_from_slice(taker.as_array());
+        }
+        data[65] = self.bump;
+
+        let instruction = InstructionView {
+            program_id: &ID,
+            accounts: &instruction_accounts,
+            data: &data,
+        };
+
+        invoke_signed(
+            &instruction,
+            &[
+                self.maker,
+                self.mint_a,
+                self.mint_b,
+                self.escrow,
+                self.maker_ata_a,
+                self.vault,
+                self.system_program,
+                self.token_program,
+                self.associated_token_program,
+            ],
+            signers,
+        )
+    }
+}
+
+/// Fills the whole offer held by `escrow`: `taker` pays `mint_b` to the maker
+/// and receives the vault's `mint_a`, and the escrow is closed. `taker` must
+/// sign.
+pub struct Take<'a> {
+    pub taker: &'a AccountView,
+    pub maker: &'a AccountView,
+    pub escrow: &'a AccountView,
+    /// Taker's `mint_a` account, credited from the vault.
+    pub taker_ata_a: &'a AccountView,
+    /// Taker's `mint_b` account the payment comes from.
+    pub taker_ata_b: &'a AccountView,
+    /// Maker's `mint_b` account the payment goes to.
+    pub maker_ata_b: &'a AccountView,
+    pub vault: &'a AccountView,
+    /// Token programs that own `mint_a` and `mint_b`.
+    pub token_program_a: &'a AccountView,
+    pub mint_a: &'a AccountView,
+    pub mint_b: &'a AccountView,
+    pub token_program_b: &'a AccountView,
+    /// The fee config at `["config"]`.
+    pub config: &'a AccountView,
+    /// `mint_b` account of the config's fee recipient. Only checked while a
+    /// fee is configured.
+    pub fee_ata_b: &'a AccountView,
+}
+
+impl Take<'_> {
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        self.invoke_with_data(&[TAKE], signers)
+    }
+
+    fn invoke_with_data(&self, data: &[u8], signers: &[Signer]) -> ProgramResult {
+        let instruction_accounts = [
+            InstructionAccount::writable_signer(self.taker.address()),
+            InstructionAccount::writable(self.maker.address()),
+            InstructionAccount::writable(self.escrow.address()),
+            InstructionAccount::writable(self.taker_ata_a.address()),
+            InstructionAccount::writable(self.taker_ata_b.address()),
+            InstructionAccount::writable(self.maker_ata_b.address()),
+            InstructionAccount::writable(self.vault.address()),
+            InstructionAccount::readonly(self.token_program_a.address()),
+            mint_a_account(self.mint_a),
+            InstructionAccount::readonly(self.mint_b.address()),
+            InstructionAccount::readonly(self.token_program_b.address()),
+            InstructionAccount::readonly(self.config.address()),
+            InstructionAccount::writable(self.fee_ata_b.address()),
+        ];
+
+        let instruction = InstructionView {
+            program_id: &ID,
+            accounts: &instruction_accounts,
+            data,
+        };
+
+        invoke_signed(
+            &instruction,
+            &[
+                self.taker,
+                self.maker,
+                self.escrow,
+                self.taker_ata_a,
+                self.taker_ata_b,
+                self.maker_ata_b,
+                self.vault,
+                self.token_program_a,
+                self.mint_a,
+                self.mint_b,
+                self.token_program_b,
+                self.config,
+                self.fee_ata_b,
+            ],
+            signers,
+        )
+    }
+}
+
+/// Pays `fill` of what is left of the offer and receives the pro-rata share of
+/// the vault. Takes the same accounts as [`Take`].
+pub struct TakePartial<'a> {
+    pub take: Take<'a>,
+    pub fill: u64,
+}
+
+impl TakePartial<'_> {
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        // [discriminator:1][fill:8]
+        let mut data = [0u8; 9];
+        data[0] = TAKE_PARTIAL;
+        data[1..9].copy_from_slice(&self.fill.to_le_bytes());
+
+        self.take.invoke_with_data(&data, signers)
+    }
+}
+
+/// Closes the offer held by `escrow`, returning the deposit to the maker.
+/// `maker` must sign.
+pub struct Cancel<'a> {
+    pub maker: &'a AccountView,
+    pub escrow: &'a AccountView,
+    /// Maker's `mint_a` account the deposit is returned to.
+    pub maker_ata_a: &'a AccountView,
+    pub vault: &'a AccountView,
+    /// Token program that owns `mint_a`.
+    pub token_program: &'a AccountView,
+    pub mint_a: &'a AccountView,
+}
+
+impl Cancel<'_> {
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let instruction_accounts = [
+            InstructionAccount::writable_signer(self.maker.address()),
+            InstructionAccount::writable(self.escrow.address()),
+            InstructionAccount::writable(self.maker_ata_a.address()),
+            InstructionAccount::writable(self.vault.address()),
+            InstructionAccount::readonly(self.token_program.address()),
+            mint_a_account(self.mint_a),
+        ];
+
+        let instruction = InstructionView {
+            program_id: &ID,
+            accounts: &instruction_accounts,
+            data: &[CANCEL],
+        };
+
+        invoke_signed(
+            &instruction,
+            &[
+                self.maker,
+                self.escrow,
+                self.maker_ata_a,
+                self.vault,
+                self.token_program,
+                self.mint_a,
+            ],
+            signers,
+        )
+    }
+}
+
+/// Writable, since closing a Token-2022 vault harvests withheld fees to the
+/// mint, unless `mint_a` is the system program standing in for native SOL.
+fn mint_a_account(mint_a: &AccountView) -> InstructionAccount<'_> {
+    if token::is_native(mint_a) {
+        InstructionAccount::readonly(mint_a.address())
+    } else {
+        InstructionAccount::writable(mint_a.address())
+    }
+}
@@ -1,18 +1,20 @@
-use pinocchio::{
-    address::declare_id, default_panic_handler, error::ProgramError, no_allocator,
-    program_entrypoint, AccountView, Address, ProgramResult,
-};
+use pinocchio::{address::declare_id, error::ProgramError, AccountView, Address, ProgramResult};
+#[cfg(not(feature = "no-entrypoint"))]
+use pinocchio::{default_panic_handler, no_allocator, program_entrypoint};
 
 use crate::instructions::r#unsafe as ix;
 use crate::instructions::wincode as ix_v2;
 
+#[cfg(not(feature = "no-entrypoint"))]
 program_entrypoint!(process_instruction);
+#[cfg(not(feature = "no-entrypoint"))]
 no_allocator!();
+#[cfg(not(feature = "no-entrypoint"))]
 default_panic_handler!();
 
 declare_id!("4ibrEMW5F6hKnkW4jVedswYv6H6VtwPN6ar6dvXDN1nT");
 
-fn process_instruction(
+pub fn process_instruction(
     program_id: &Address,
     accounts: &[AccountView],
     instruction_data: &[u8],
@@ -28,6 +30,18 @@ fn process_instruction(
         Some((3, rest)) => ix_v2::process_make_instruction(accounts, rest),
         Some((4, rest)) => ix_v2::process_take_instruction(accounts, rest),
         Some((5, rest)) => ix_v2::process_cancel_instruction(accounts, rest),
+        Some((6, rest)) => ix::process_take_partial_instruction(accounts, rest),
+        Some((7, rest)) => ix_v2::process_take_partial_instruction(accounts, rest),
+        Some((8, rest)) => ix::process_reclaim_instruction(accounts, rest),
+        Some((9, rest)) => ix_v2::process_reclaim_instruction(accounts, rest),
+        Some((10, rest)) => ix::process_amend_instruction(accounts, rest),
+        Some((11, rest)) => ix_v2::process_amend_instruction(accounts, rest),
+        Some((12, rest)) => ix::process_set_config_instruction(accounts, rest),
+        Some((13, rest)) => ix_v2::process_set_config_instruction(accounts, rest),
+        Some((14, rest)) => ix::process_take_many_instruction(accounts, rest),
+        Some((15, rest)) => ix_v2::process_take_many_instruction(accounts, rest),
+        Some((16, rest)) => ix::process_migrate_instruction(accounts, rest),
+        Some((17, rest)) => ix_v2::process_migrate_instruction(accounts, rest),
         _ => Err(ProgramError::InvalidInstructionData),
     }
 }
@@ -0,0 +1,135 @@
+//! Binary event records, logged so indexers can follow offers from
+//! transaction logs instead of diffing accounts.
+//!
+//! Each event is a single `sol_log_data` entry, which the runtime logs as
+//! `Program data: <base64>`: a discriminator byte, then the fields in order,
+//! addresses as 32 bytes and amounts as little-endian `u64`. The
+//! `escrow-events` crate decodes them.
+
+use pinocchio::{AccountView, Address};
+
+mod layout;
+
+pub use layout::{AMENDED, CANCELLED, MADE, TAKEN};
+use layout::{AMENDED_LEN, CANCELLED_LEN, MADE_LEN, TAKEN_LEN};
+
+/// An offer was opened with `amount_to_give` of `mint_a` in its vault.
+pub fn made(
+    escrow: &AccountView,
+    maker: &AccountView,
+    mint_a: &AccountView,
+    mint_b: &AccountView,
+    amount_to_receive: u64,
+    amount_to_give: u64,
+) {
+    Record::<MADE_LEN>::new(MADE)
+        .address(escrow.address())
+        .address(maker.address())
+        .address(mint_a.address())
+        .address(mint_b.address())
+        .amount(amount_to_receive)
+        .amount(amount_to_give)
+        .emit();
+}
+
+/// `taker` paid `amount_paid` of `mint_b`, `fee` of it to the fee recipient,
+/// and `amount_received` of `mint_a` left the vault for them. Both amounts
+/// exclude Token-2022 transfer fees. Logged for whole and partial fills alike.
+#[allow(clippy::too_many_arguments)]
+pub fn taken(
+    escrow: &AccountView,
+    maker: &AccountView,
+    taker: &AccountView,
+    mint_a: &AccountView,
+    mint_b: &AccountView,
+    amount_paid: u64,
+    amount_received: u64,
+    fee: u64,
+) {
+    Record::<TAKEN_LEN>::new(TAKEN)
+        .address(escrow.address())
+        .address(maker.address())
+        .address(taker.address())
+        .address(mint_a.address())
+        .address(mint_b.address())
+        .amount(amount_paid)
+        .amount(amount_received)
+        .amount(fee)
+        .emit();
+}
+
+/// The offer was closed and `amount_returned` of `mint_a` went back to the
+/// maker, by `cancel` or by `reclaim` after expiry.
+pub fn cancelled(
+    escrow: &AccountView,
+    maker: &AccountView,
+    mint_a: &AccountView,
+    amount_returned: u64,
+) {
+    Record::<CANCELLED_LEN>::new(CANCELLED)
+        .address(escrow.address())
+        .address(maker.address())
+        .address(mint_a.address())
+        .amount(amount_returned)
+        .emit();
+}
+
+/// `amend` changed the offer's terms to `amount_to_receive` of `mint_b` for
+/// `amount_to_give` of `mint_a`, and the vault now holds `amount_to_give`.
+pub fn amended(
+    escrow: &AccountView,
+    maker: &AccountView,
+    mint_a: &AccountView,
+    amount_to_receive: u64,
+    amount_to_give: u64,
+) {
+    Record::<AMENDED_LEN>::new(AMENDED)
+        .address(escrow.address())
+        .address(maker.address())
+        .address(mint_a.address())
+        .amount(amount_to_receive)
+        .amount(amount_to_give)
+        .emit();
+}
+
+struct Record<const LEN: usize> {
+    data: [u8; LEN],
+    offset: usize,
+}
+
+impl<const LEN: usize> Record<LEN> {
+    fn new(discriminator: u8) -> Self {
+        let mut data = [0; LEN];
+        data[0] = discriminator;
+        Self { data, offset: 1 }
+    }
+
+    fn address(self, address: &Address) -> Self {
+        self.bytes(address.as_array())
+    }
+
+    fn amount(self, amount: u64) -> Self {
+        self.bytes(&amount.to_le_bytes())
+    }
+
+    fn bytes(mut self, bytes: &[u8]) -> Self {
+        self.data[self.offset..self.offset + bytes.len()].copy_from_slice(bytes);
+        self.offset += bytes.len();
+        self
+    }
+
+    fn emit(self) {
+        debug_assert_eq!(self.offset, LEN);
+        log_data(&[&self.data]);
+    }
+}
+
+fn log_data(data: &[&[u8]]) {
+    #[cfg(target_os = "solana")]
+    unsafe {
+        pinocchio::syscalls::sol_log_data(data.as_ptr() as *const u8, data.len() as u64);
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    core::hint::black_box(data);
+}
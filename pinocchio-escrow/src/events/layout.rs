@@ -0,0 +1,16 @@
+//! Discriminators and lengths of the event records. The `escrow-events`
+//! decoder includes this file as well, so the two cannot drift apart.
+
+pub const MADE: u8 = 0;
+pub const TAKEN: u8 = 1;
+pub const CANCELLED: u8 = 2;
+pub const AMENDED: u8 = 3;
+
+/// `[escrow][maker][mint_a][mint_b][amount_to_receive][amount_to_give]`
+pub const MADE_LEN: usize = 1 + 4 * 32 + 2 * 8;
+/// `[escrow][maker][taker][mint_a][mint_b][amount_paid][amount_received][fee]`
+pub const TAKEN_LEN: usize = 1 + 5 * 32 + 3 * 8;
+/// `[escrow][maker][mint_a][amount_returned]`
+pub const CANCELLED_LEN: usize = 1 + 3 * 32 + 8;
+/// `[escrow][maker][mint_a][amount_to_receive][amount_to_give]`
+pub const AMENDED_LEN: usize = 1 + 3 * 32 + 2 * 8;
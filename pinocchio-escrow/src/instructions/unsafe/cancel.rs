@@ -5,12 +5,15 @@ use pinocchio::{
     AccountView, ProgramResult,
 };
 use pinocchio_pubkey::derive_address;
-use pinocchio_token::instructions::{CloseAccount, Transfer};
 
+use crate::events;
 use crate::state::Escrow;
+use crate::token;
 
 pub fn process_cancel_instruction(accounts: &[AccountView], _data: &[u8]) -> ProgramResult {
-    let [maker, escrow_account, maker_ata_a, escrow_ata, _remaining @ ..] = accounts else {
+    let [maker, escrow_account, maker_ata_a, escrow_ata, token_program, mint_a, _remaining @ ..] =
+        accounts
+    else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
@@ -18,18 +21,37 @@ pub fn process_cancel_instruction(accounts: &[AccountView], _data: &[u8]) -> Pro
         return Err(ProgramError::IncorrectAuthority);
     }
 
-    let (bump, amount_to_give) = {
+    if !escrow_account.owned_by(&ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let (seed, bump, amount_to_give) = {
         let escrow_data = unsafe { escrow_account.borrow_unchecked() };
         let escrow_state = Escrow::load(escrow_data)?;
+        escrow_state.check_header()?;
 
         if escrow_state.maker != *maker.address().as_array() {
             return Err(ProgramError::InvalidAccountData);
         }
 
-        (escrow_state.bump, escrow_state.amount_to_give)
+        if mint_a.address().as_array() != &escrow_state.mint_a {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        (
+            escrow_state.seed,
+            escrow_state.bump,
+            escrow_state.amount_to_give,
+        )
     };
 
-    let seeds: [&[u8]; 3] = [b"escrow", maker.address().as_array(), &[bump]];
+    token::check_token_account(maker_ata_a, mint_a, None)?;
+    token::check_token_account(escrow_ata, mint_a, Some(escrow_account.address()))?;
+
+    token::check_token_program(token_program, mint_a)?;
+
+    let seed_bytes = seed.to_le_bytes();
+    let seeds: [&[u8]; 4] = [b"escrow", maker.address().as_array(), &seed_bytes, &[bump]];
     let expected_escrow = derive_address(&seeds, None, ID.as_array());
 
     if escrow_account.address().as_array() != &expected_escrow {
@@ -40,27 +62,33 @@ pub fn process_cancel_instruction(accounts: &[AccountView], _data: &[u8]) -> Pro
     let signer_seeds = [
         Seed::from(b"escrow"),
         Seed::from(maker.address().as_array()),
+        Seed::from(&seed_bytes),
         Seed::from(&bump_seed),
     ];
 
-    Transfer {
-        from: escrow_ata,
-        to: maker_ata_a,
-        authority: escrow_account,
-        amount: amount_to_give,
-    }
-    .invoke_signed(&[Signer::from(&signer_seeds[..])])?;
+    token::transfer_checked(
+        token_program,
+        escrow_ata,
+        mint_a,
+        maker_ata_a,
+        escrow_account,
+        amount_to_give,
+        token::mint_decimals(mint_a)?,
+        &[Signer::from(&signer_seeds[..])],
+    )?;
 
-    CloseAccount {
-        account: escrow_ata,
-        destination: maker,
-        authority: escrow_account,
-    }
-    .invoke_signed(&[Signer::from(&signer_seeds[..])])?;
+    token::close_vault(
+        token_program,
+        escrow_ata,
+        mint_a,
+        maker,
+        escrow_account,
+        &[Signer::from(&signer_seeds[..])],
+    )?;
+
+    Escrow::close(escrow_account, maker)?;
 
-    let escrow_lamports = escrow_account.lamports();
-    maker.set_lamports(maker.lamports() + escrow_lamports);
-    escrow_account.set_lamports(0);
+    events::cancelled(escrow_account, maker, mint_a, amount_to_give);
 
     Ok(())
 }
@@ -2,15 +2,16 @@ use crate::entrypoint::ID;
 use pinocchio::{
     cpi::{Seed, Signer},
     error::ProgramError,
-    sysvars::{rent::Rent, Sysvar},
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
     AccountView, ProgramResult,
 };
 use pinocchio_associated_token_account::instructions::Create;
 use pinocchio_pubkey::derive_address;
 use pinocchio_system::instructions::CreateAccount;
-use pinocchio_token::instructions::Transfer;
 
+use crate::events;
 use crate::state::Escrow;
+use crate::token;
 
 pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
     let [maker, mint_a, mint_b, escrow_account, maker_ata, escrow_ata, system_program, token_program, _remaining @ ..] =
@@ -23,29 +24,40 @@ pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> Progra
         return Err(ProgramError::IncorrectAuthority);
     }
 
-    if data.len() < 17 {
+    if data.len() < 65 {
         return Err(ProgramError::InvalidInstructionData);
     }
 
-    let amount_to_receive = u64::from_le_bytes([
+    let seed = u64::from_le_bytes([
         data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7],
     ]);
-    let amount_to_give = u64::from_le_bytes([
+    let amount_to_receive = u64::from_le_bytes([
         data[8], data[9], data[10], data[11], data[12], data[13], data[14], data[15],
     ]);
-    let bump = data[16];
+    let amount_to_give = u64::from_le_bytes([
+        data[16], data[17], data[18], data[19], data[20], data[21], data[22], data[23],
+    ]);
+    let duration = i64::from_le_bytes([
+        data[24], data[25], data[26], data[27], data[28], data[29], data[30], data[31],
+    ]);
+    let taker: [u8; 32] = data[32..64].try_into().unwrap();
+    let bump = data[64];
 
-    {
-        let maker_ata_state = pinocchio_token::state::TokenAccount::from_account_view(maker_ata)?;
-        if maker_ata_state.owner() != maker.address() {
-            return Err(ProgramError::IllegalOwner);
-        }
-        if maker_ata_state.mint() != mint_a.address() {
-            return Err(ProgramError::InvalidAccountData);
-        }
+    if duration <= 0 {
+        return Err(ProgramError::InvalidInstructionData);
     }
+    let clock = Clock::get()?;
+    let expires_at = clock
+        .unix_timestamp
+        .checked_add(duration)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
 
-    let seeds: [&[u8]; 3] = [b"escrow", maker.address().as_array(), &[bump]];
+    token::check_token_account(maker_ata, mint_a, Some(maker.address()))?;
+
+    token::check_token_program(token_program, mint_a)?;
+
+    let seed_bytes = seed.to_le_bytes();
+    let seeds: [&[u8]; 4] = [b"escrow", maker.address().as_array(), &seed_bytes, &[bump]];
     let expected_escrow = derive_address(&seeds, None, ID.as_array());
 
     if escrow_account.address().as_array() != &expected_escrow {
@@ -56,6 +68,7 @@ pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> Progra
     let signer_seeds = [
         Seed::from(b"escrow"),
         Seed::from(maker.address().as_array()),
+        Seed::from(&seed_bytes),
         Seed::from(&bump_seed),
     ];
     let signer = Signer::from(&signer_seeds[..]);
@@ -72,30 +85,54 @@ pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> Progra
     let escrow_data = unsafe { escrow_account.borrow_unchecked_mut() };
     let escrow_state = Escrow::load_mut(escrow_data)?;
 
+    escrow_state.discriminator = Escrow::DISCRIMINATOR;
+    escrow_state.version = Escrow::VERSION;
+    escrow_state.bump = bump;
     escrow_state.maker = *maker.address().as_array();
     escrow_state.mint_a = *mint_a.address().as_array();
     escrow_state.mint_b = *mint_b.address().as_array();
+    escrow_state.taker = taker;
+    escrow_state.seed = seed;
     escrow_state.amount_to_receive = amount_to_receive;
     escrow_state.amount_to_give = amount_to_give;
-    escrow_state.bump = bump;
+    escrow_state.expires_at = expires_at;
 
-    Create {
-        funding_account: maker,
-        account: escrow_ata,
-        wallet: escrow_account,
-        mint: mint_a,
-        token_program,
-        system_program,
+    // A native deposit is held by the escrow account itself.
+    if token::is_native(mint_a) {
+        token::check_token_account(escrow_ata, mint_a, Some(escrow_account.address()))?;
+    } else {
+        Create {
+            funding_account: maker,
+            account: escrow_ata,
+            wallet: escrow_account,
+            mint: mint_a,
+            token_program,
+            system_program,
+        }
+        .invoke()?;
     }
-    .invoke()?;
 
-    Transfer {
-        from: maker_ata,
-        to: escrow_ata,
-        authority: maker,
-        amount: amount_to_give,
-    }
-    .invoke()?;
+    // The vault has to end up holding `amount_to_give`, so the maker pays
+    // any transfer fee on the deposit.
+    token::transfer_checked(
+        token_program,
+        maker_ata,
+        mint_a,
+        escrow_ata,
+        maker,
+        token::amount_with_fee(mint_a, amount_to_give, clock.epoch)?,
+        token::mint_decimals(mint_a)?,
+        &[],
+    )?;
+
+    events::made(
+        escrow_account,
+        maker,
+        mint_a,
+        mint_b,
+        amount_to_receive,
+        amount_to_give,
+    );
 
     Ok(())
 }
@@ -1,7 +1,19 @@
+mod amend;
 mod cancel;
 mod make;
+mod migrate;
+mod reclaim;
+mod set_config;
 mod take;
+mod take_many;
+mod take_partial;
 
+pub use amend::process_amend_instruction;
 pub use cancel::process_cancel_instruction;
 pub use make::process_make_instruction;
+pub use migrate::process_migrate_instruction;
+pub use reclaim::process_reclaim_instruction;
+pub use set_config::process_set_config_instruction;
 pub use take::process_take_instruction;
+pub use take_many::process_take_many_instruction;
+pub use take_partial::process_take_partial_instruction;
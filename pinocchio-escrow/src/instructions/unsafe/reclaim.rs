@@ -0,0 +1,98 @@
+use crate::entrypoint::ID;
+use pinocchio::{
+    cpi::{Seed, Signer},
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+use pinocchio_pubkey::derive_address;
+
+use crate::events;
+use crate::state::Escrow;
+use crate::token;
+
+/// Returns an expired offer to its maker. Anyone may send it, so the maker
+/// does not sign; instead the refund destination is checked to be the
+/// maker's own token account for `mint_a`.
+pub fn process_reclaim_instruction(accounts: &[AccountView], _data: &[u8]) -> ProgramResult {
+    let [maker, escrow_account, maker_ata_a, escrow_ata, token_program, mint_a, _remaining @ ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !escrow_account.owned_by(&ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let (seed, bump, amount_to_give) = {
+        let escrow_data = unsafe { escrow_account.borrow_unchecked() };
+        let escrow_state = Escrow::load(escrow_data)?;
+        escrow_state.check_header()?;
+
+        if escrow_state.maker != *maker.address().as_array() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if mint_a.address().as_array() != &escrow_state.mint_a {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if Clock::get()?.unix_timestamp < escrow_state.expires_at {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        (
+            escrow_state.seed,
+            escrow_state.bump,
+            escrow_state.amount_to_give,
+        )
+    };
+
+    token::check_token_account(maker_ata_a, mint_a, Some(maker.address()))?;
+    token::check_token_account(escrow_ata, mint_a, Some(escrow_account.address()))?;
+
+    token::check_token_program(token_program, mint_a)?;
+
+    let seed_bytes = seed.to_le_bytes();
+    let seeds: [&[u8]; 4] = [b"escrow", maker.address().as_array(), &seed_bytes, &[bump]];
+    let expected_escrow = derive_address(&seeds, None, ID.as_array());
+
+    if escrow_account.address().as_array() != &expected_escrow {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let bump_seed = [bump];
+    let signer_seeds = [
+        Seed::from(b"escrow"),
+        Seed::from(maker.address().as_array()),
+        Seed::from(&seed_bytes),
+        Seed::from(&bump_seed),
+    ];
+
+    token::transfer_checked(
+        token_program,
+        escrow_ata,
+        mint_a,
+        maker_ata_a,
+        escrow_account,
+        amount_to_give,
+        token::mint_decimals(mint_a)?,
+        &[Signer::from(&signer_seeds[..])],
+    )?;
+
+    token::close_vault(
+        token_program,
+        escrow_ata,
+        mint_a,
+        maker,
+        escrow_account,
+        &[Signer::from(&signer_seeds[..])],
+    )?;
+
+    Escrow::close(escrow_account, maker)?;
+
+    events::cancelled(escrow_account, maker, mint_a, amount_to_give);
+
+    Ok(())
+}
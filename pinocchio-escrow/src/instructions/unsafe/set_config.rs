@@ -0,0 +1,93 @@
+use crate::entrypoint::ID;
+use pinocchio::{
+    cpi::{Seed, Signer},
+    error::ProgramError,
+    sysvars::{rent::Rent, Sysvar},
+    AccountView, ProgramResult,
+};
+use pinocchio_system::instructions::{Allocate, Assign, Transfer};
+
+use crate::state::Config;
+
+/// Creates the fee config on first use, signed and paid for by the program's
+/// upgrade authority, and updates it afterwards, which only the stored admin
+/// may do.
+pub fn process_set_config_instruction(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let [authority, config_account, _system_program, program_data, _remaining @ ..] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+
+    if data.len() < 66 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let admin: [u8; 32] = data[0..32].try_into().unwrap();
+    let fee_recipient: [u8; 32] = data[32..64].try_into().unwrap();
+    let fee_basis_points = u16::from_le_bytes([data[64], data[65]]);
+
+    if fee_basis_points > Config::MAX_FEE_BASIS_POINTS {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    if config_account.address() != &Config::ADDRESS {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if config_account.owned_by(&ID) {
+        let config_data = unsafe { config_account.borrow_unchecked() };
+        let config = Config::load(config_data)?;
+        config.check_header()?;
+
+        if config.admin != *authority.address().as_array() {
+            return Err(ProgramError::IncorrectAuthority);
+        }
+    } else {
+        Config::check_upgrade_authority(program_data, authority)?;
+
+        let bump_seed = [Config::BUMP];
+        let signer_seeds = [Seed::from(b"config"), Seed::from(&bump_seed)];
+        let signers = [Signer::from(&signer_seeds[..])];
+
+        // `CreateAccount` fails once the address holds lamports, which anyone
+        // can send it, so top it up to rent exemption and allocate it instead.
+        let rent = Rent::get()?.minimum_balance_unchecked(Config::LEN);
+        let lamports = rent.saturating_sub(config_account.lamports());
+        if lamports > 0 {
+            Transfer {
+                from: authority,
+                to: config_account,
+                lamports,
+            }
+            .invoke()?;
+        }
+
+        Allocate {
+            account: config_account,
+            space: Config::LEN as u64,
+        }
+        .invoke_signed(&signers)?;
+
+        Assign {
+            account: config_account,
+            owner: &ID,
+        }
+        .invoke_signed(&signers)?;
+    }
+
+    let config_data = unsafe { config_account.borrow_unchecked_mut() };
+    let config = Config::load_mut(config_data)?;
+
+    config.discriminator = Config::DISCRIMINATOR;
+    config.version = Config::VERSION;
+    config.bump = Config::BUMP;
+    config.admin = admin;
+    config.fee_recipient = fee_recipient;
+    config.fee_basis_points = fee_basis_points;
+
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use crate::entrypoint::ID;
+use pinocchio::{
+    cpi::{Seed, Signer},
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, Address, ProgramResult,
+};
+use pinocchio_pubkey::derive_address;
+
+use crate::events;
+use crate::state::{Config, Escrow};
+use crate::token;
+
+pub fn process_take_partial_instruction(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let [taker, maker, escrow_account, taker_ata_a, taker_ata_b, maker_ata_b, escrow_ata, token_program_a, mint_a, mint_b, token_program_b, config_account, fee_ata_b, _remaining @ ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !taker.is_signer() {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+
+    if data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let fill = u64::from_le_bytes([
+        data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7],
+    ]);
+
+    let clock = Clock::get()?;
+
+    if !escrow_account.owned_by(&ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let (amount_to_release, remaining_to_receive, seed, bump) = {
+        let escrow_data = unsafe { escrow_account.borrow_unchecked() };
+        let escrow_state = Escrow::load(escrow_data)?;
+        escrow_state.check_header()?;
+
+        if escrow_state.maker != *maker.address().as_array() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        escrow_state.check_taker(taker.address())?;
+
+        if mint_a.address().as_array() != &escrow_state.mint_a
+            || mint_b.address().as_array() != &escrow_state.mint_b
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if clock.unix_timestamp >= escrow_state.expires_at {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        (
+            escrow_state.amount_for_fill(fill)?,
+            escrow_state.amount_to_receive - fill,
+            escrow_state.seed,
+            escrow_state.bump,
+        )
+    };
+
+    token::check_token_account(maker_ata_b, mint_b, Some(maker.address()))?;
+    token::check_token_account(taker_ata_a, mint_a, None)?;
+    token::check_token_account(taker_ata_b, mint_b, Some(taker.address()))?;
+    token::check_token_account(escrow_ata, mint_a, Some(escrow_account.address()))?;
+
+    token::check_token_program(token_program_a, mint_a)?;
+    token::check_token_program(token_program_b, mint_b)?;
+
+    if config_account.address() != &Config::ADDRESS {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // No fee is charged until the config has been created.
+    let (fee, fee_recipient) = if config_account.owned_by(&ID) {
+        let config_data = unsafe { config_account.borrow_unchecked() };
+        let config = Config::load(config_data)?;
+        config.check_header()?;
+
+        (config.fee_for(fill), config.fee_recipient)
+    } else {
+        (0, [0; 32])
+    };
+
+    if fee > 0 {
+        token::check_token_account(
+            fee_ata_b,
+            mint_b,
+            Some(&Address::new_from_array(fee_recipient)),
+        )?;
+    }
+
+    let seed_bytes = seed.to_le_bytes();
+    let seeds: [&[u8]; 4] = [b"escrow", maker.address().as_array(), &seed_bytes, &[bump]];
+    let expected_escrow = derive_address(&seeds, None, ID.as_array());
+
+    if escrow_account.address().as_array() != &expected_escrow {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let bump_seed = [bump];
+    let signer_seeds = [
+        Seed::from(b"escrow"),
+        Seed::from(maker.address().as_array()),
+        Seed::from(&seed_bytes),
+        Seed::from(&bump_seed),
+    ];
+
+    // The protocol fee comes out of the maker's proceeds. Both legs are
+    // grossed up by any transfer fee, so the maker and the fee recipient are
+    // credited exactly their share and the taker pays transfer fees on top.
+    let decimals_b = token::mint_decimals(mint_b)?;
+
+    if fee > 0 {
+        token::transfer_checked(
+            token_program_b,
+            taker_ata_b,
+            mint_b,
+            fee_ata_b,
+            taker,
+            token::amount_with_fee(mint_b, fee, clock.epoch)?,
+            decimals_b,
+            &[],
+        )?;
+    }
+
+    token::transfer_checked(
+        token_program_b,
+        taker_ata_b,
+        mint_b,
+        maker_ata_b,
+        taker,
+        token::amount_with_fee(mint_b, fill - fee, clock.epoch)?,
+        decimals_b,
+        &[],
+    )?;
+
+    token::transfer_checked(
+        token_program_a,
+        escrow_ata,
+        mint_a,
+        taker_ata_a,
+        escrow_account,
+        amount_to_release,
+        token::mint_decimals(mint_a)?,
+        &[Signer::from(&signer_seeds[..])],
+    )?;
+
+    events::taken(
+        escrow_account,
+        maker,
+        taker,
+        mint_a,
+        mint_b,
+        fill,
+        amount_to_release,
+        fee,
+    );
+
+    if remaining_to_receive > 0 {
+        let escrow_data = unsafe { escrow_account.borrow_unchecked_mut() };
+        let escrow_state = Escrow::load_mut(escrow_data)?;
+
+        escrow_state.amount_to_receive = remaining_to_receive;
+        escrow_state.amount_to_give -= amount_to_release;
+
+        return Ok(());
+    }
+
+    token::close_vault(
+        token_program_a,
+        escrow_ata,
+        mint_a,
+        maker,
+        escrow_account,
+        &[Signer::from(&signer_seeds[..])],
+    )?;
+
+    Escrow::close(escrow_account, maker)?;
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use core::cmp::Ordering;
+
+use crate::entrypoint::ID;
+use ::wincode::SchemaRead;
+use pinocchio::{
+    cpi::{Seed, Signer},
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+use pinocchio_pubkey::derive_address;
+
+use crate::events;
+use crate::state::Escrow;
+use crate::token;
+
+#[derive(SchemaRead)]
+pub struct AmendInstructionData {
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+}
+
+/// Changes the price of an open offer and resizes its deposit. The vault is
+/// topped up from, or partly refunded to, `maker_ata_a` so it keeps holding
+/// exactly the new `amount_to_give`.
+pub fn process_amend_instruction(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let [maker, escrow_account, maker_ata_a, escrow_ata, token_program, mint_a, _remaining @ ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !maker.is_signer() {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+
+    let ix_data = ::wincode::deserialize::<AmendInstructionData>(data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let amount_to_receive = ix_data.amount_to_receive;
+    let amount_to_give = ix_data.amount_to_give;
+
+    // Emptying the offer is what `cancel` is for.
+    if amount_to_receive == 0 || amount_to_give == 0 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let clock = Clock::get()?;
+
+    if !escrow_account.owned_by(&ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let escrow_data = unsafe { escrow_account.borrow_unchecked() };
+    let escrow_state = ::wincode::deserialize::<Escrow>(escrow_data)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    escrow_state.check_header()?;
+
+    if escrow_state.maker != *maker.address().as_array() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if mint_a.address().as_array() != &escrow_state.mint_a {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if clock.unix_timestamp >= escrow_state.expires_at {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let seed = escrow_state.seed;
+    let bump = escrow_state.bump;
+    let deposited = escrow_state.amount_to_give;
+
+    token::check_token_account(maker_ata_a, mint_a, None)?;
+    token::check_token_account(escrow_ata, mint_a, Some(escrow_account.address()))?;
+
+    token::check_token_program(token_program, mint_a)?;
+
+    let seed_bytes = seed.to_le_bytes();
+    let seeds: [&[u8]; 4] = [b"escrow", maker.address().as_array(), &seed_bytes, &[bump]];
+    let expected_escrow = derive_address(&seeds, None, ID.as_array());
+
+    if escrow_account.address().as_array() != &expected_escrow {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    match amount_to_give.cmp(&deposited) {
+        Ordering::Greater => {
+            // Like the deposit in `make`, the maker pays any transfer fee so the
+            // vault grows by exactly the difference.
+            token::transfer_checked(
+                token_program,
+                maker_ata_a,
+                mint_a,
+                escrow_ata,
+                maker,
+                token::amount_with_fee(mint_a, amount_to_give - deposited, clock.epoch)?,
+                token::mint_decimals(mint_a)?,
+                &[],
+            )?;
+        }
+        Ordering::Less => {
+            let bump_seed = [bump];
+            let signer_seeds = [
+                Seed::from(b"escrow"),
+                Seed::from(maker.address().as_array()),
+                Seed::from(&seed_bytes),
+                Seed::from(&bump_seed),
+            ];
+
+            token::transfer_checked(
+                token_program,
+                escrow_ata,
+                mint_a,
+                maker_ata_a,
+                escrow_account,
+                deposited - amount_to_give,
+                token::mint_decimals(mint_a)?,
+                &[Signer::from(&signer_seeds[..])],
+            )?;
+        }
+        Ordering::Equal => {}
+    }
+
+    let escrow_data = unsafe { escrow_account.borrow_unchecked_mut() };
+    let escrow_state = Escrow::load_mut(escrow_data)?;
+
+    escrow_state.amount_to_receive = amount_to_receive;
+    escrow_state.amount_to_give = amount_to_give;
+
+    events::amended(
+        escrow_account,
+        maker,
+        mint_a,
+        amount_to_receive,
+        amount_to_give,
+    );
+
+    Ok(())
+}
@@ -2,21 +2,26 @@ use crate::entrypoint::ID;
 use pinocchio::{
     cpi::{Seed, Signer},
     error::ProgramError,
-    sysvars::{rent::Rent, Sysvar},
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
     AccountView, ProgramResult,
 };
 use pinocchio_associated_token_account::instructions::Create;
 use pinocchio_pubkey::derive_address;
 use pinocchio_system::instructions::CreateAccount;
-use pinocchio_token::instructions::Transfer;
 use ::wincode::SchemaRead;
 
+use crate::events;
 use crate::state::Escrow;
+use crate::token;
 
 #[derive(SchemaRead)]
 pub struct MakeInstructionData {
+    pub seed: u64,
     pub amount_to_receive: u64,
     pub amount_to_give: u64,
+    pub duration: i64,
+    /// Only key allowed to take the offer; all zero for a public offer.
+    pub taker: [u8; 32],
     pub bump: u8,
 }
 
@@ -34,21 +39,26 @@ pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> Progra
     let ix_data = ::wincode::deserialize::<MakeInstructionData>(data)
         .map_err(|_| ProgramError::InvalidInstructionData)?;
 
+    let seed = ix_data.seed;
     let bump = ix_data.bump;
     let amount_to_receive = ix_data.amount_to_receive;
     let amount_to_give = ix_data.amount_to_give;
 
-    {
-        let maker_ata_state = pinocchio_token::state::TokenAccount::from_account_view(maker_ata)?;
-        if maker_ata_state.owner() != maker.address() {
-            return Err(ProgramError::IllegalOwner);
-        }
-        if maker_ata_state.mint() != mint_a.address() {
-            return Err(ProgramError::InvalidAccountData);
-        }
+    if ix_data.duration <= 0 {
+        return Err(ProgramError::InvalidInstructionData);
     }
+    let clock = Clock::get()?;
+    let expires_at = clock
+        .unix_timestamp
+        .checked_add(ix_data.duration)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    token::check_token_account(maker_ata, mint_a, Some(maker.address()))?;
 
-    let seeds: [&[u8]; 3] = [b"escrow", maker.address().as_array(), &[bump]];
+    token::check_token_program(token_program, mint_a)?;
+
+    let seed_bytes = seed.to_le_bytes();
+    let seeds: [&[u8]; 4] = [b"escrow", maker.address().as_array(), &seed_bytes, &[bump]];
     let expected_escrow = derive_address(&seeds, None, ID.as_array());
 
     if escrow_account.address().as_array() != &expected_escrow {
@@ -59,6 +69,7 @@ pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> Progra
     let signer_seeds = [
         Seed::from(b"escrow"),
         Seed::from(maker.address().as_array()),
+        Seed::from(&seed_bytes),
         Seed::from(&bump_seed),
     ];
     let signer = Signer::from(&signer_seeds[..]);
@@ -75,30 +86,54 @@ pub fn process_make_instruction(accounts: &[AccountView], data: &[u8]) -> Progra
     let escrow_data = unsafe { escrow_account.borrow_unchecked_mut() };
     let escrow_state = Escrow::load_mut(escrow_data)?;
 
+    escrow_state.discriminator = Escrow::DISCRIMINATOR;
+    escrow_state.version = Escrow::VERSION;
+    escrow_state.bump = bump;
     escrow_state.maker = *maker.address().as_array();
     escrow_state.mint_a = *mint_a.address().as_array();
     escrow_state.mint_b = *mint_b.address().as_array();
+    escrow_state.taker = ix_data.taker;
+    escrow_state.seed = seed;
     escrow_state.amount_to_receive = amount_to_receive;
     escrow_state.amount_to_give = amount_to_give;
-    escrow_state.bump = bump;
-
-    Create {
-        funding_account: maker,
-        account: escrow_ata,
-        wallet: escrow_account,
-        mint: mint_a,
-        token_program,
-        system_program,
+    escrow_state.expires_at = expires_at;
+
+    // A native deposit is held by the escrow account itself.
+    if token::is_native(mint_a) {
+        token::check_token_account(escrow_ata, mint_a, Some(escrow_account.address()))?;
+    } else {
+        Create {
+            funding_account: maker,
+            account: escrow_ata,
+            wallet: escrow_account,
+            mint: mint_a,
+            token_program,
+            system_program,
+        }
+        .invoke()?;
     }
-    .invoke()?;
 
-    Transfer {
-        from: maker_ata,
-        to: escrow_ata,
-        authority: maker,
-        amount: amount_to_give,
-    }
-    .invoke()?;
+    // The vault has to end up holding `amount_to_give`, so the maker pays
+    // any transfer fee on the deposit.
+    token::transfer_checked(
+        token_program,
+        maker_ata,
+        mint_a,
+        escrow_ata,
+        maker,
+        token::amount_with_fee(mint_a, amount_to_give, clock.epoch)?,
+        token::mint_decimals(mint_a)?,
+        &[],
+    )?;
+
+    events::made(
+        escrow_account,
+        maker,
+        mint_a,
+        mint_b,
+        amount_to_receive,
+        amount_to_give,
+    );
 
     Ok(())
 }
@@ -0,0 +1,65 @@
+use crate::entrypoint::ID;
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{rent::Rent, Sysvar},
+    AccountView, ProgramResult,
+};
+use pinocchio_system::instructions::Transfer;
+
+use crate::state::{Escrow, EscrowV1};
+
+/// Rewrites a version 1 escrow in the current layout as a public offer, with
+/// `payer` covering the rent of the extra space. Anyone may call it, since the
+/// offer's terms do not change.
+pub fn process_migrate_instruction(accounts: &[AccountView], _data: &[u8]) -> ProgramResult {
+    let [payer, escrow_account, _system_program, _remaining @ ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !payer.is_signer() {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+
+    if !escrow_account.owned_by(&ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let old = {
+        let escrow_data = unsafe { escrow_account.borrow_unchecked() };
+        let old = ::wincode::deserialize::<EscrowV1>(escrow_data)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        old.check_header()?;
+        old
+    };
+
+    // The account already holds the rent for its old size.
+    let rent = Rent::get()?;
+    let lamports = rent
+        .minimum_balance_unchecked(Escrow::LEN)
+        .saturating_sub(rent.minimum_balance_unchecked(EscrowV1::LEN));
+    Transfer {
+        from: payer,
+        to: escrow_account,
+        lamports,
+    }
+    .invoke()?;
+
+    escrow_account.resize(Escrow::LEN)?;
+
+    let escrow_data = unsafe { escrow_account.borrow_unchecked_mut() };
+    let escrow_state = Escrow::load_mut(escrow_data)?;
+
+    escrow_state.discriminator = Escrow::DISCRIMINATOR;
+    escrow_state.version = Escrow::VERSION;
+    escrow_state.bump = old.bump;
+    escrow_state.maker = old.maker;
+    escrow_state.mint_a = old.mint_a;
+    escrow_state.mint_b = old.mint_b;
+    escrow_state.taker = [0; 32];
+    escrow_state.seed = old.seed;
+    escrow_state.amount_to_receive = old.amount_to_receive;
+    escrow_state.amount_to_give = old.amount_to_give;
+    escrow_state.expires_at = old.expires_at;
+
+    Ok(())
+}
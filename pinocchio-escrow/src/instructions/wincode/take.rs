@@ -2,18 +2,17 @@ use crate::entrypoint::ID;
 use pinocchio::{
     cpi::{Seed, Signer},
     error::ProgramError,
-    AccountView, ProgramResult,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, Address, ProgramResult,
 };
 use pinocchio_pubkey::derive_address;
-use pinocchio_token::{
-    instructions::{CloseAccount, Transfer},
-    state::TokenAccount,
-};
 
-use crate::state::Escrow;
+use crate::events;
+use crate::state::{Config, Escrow};
+use crate::token;
 
 pub fn process_take_instruction(accounts: &[AccountView], _data: &[u8]) -> ProgramResult {
-    let [taker, maker, escrow_account, taker_ata_a, taker_ata_b, maker_ata_b, escrow_ata, _remaining @ ..] =
+    let [taker, maker, escrow_account, taker_ata_a, taker_ata_b, maker_ata_b, escrow_ata, token_program_a, mint_a, mint_b, token_program_b, config_account, fee_ata_b, _remaining @ ..] =
         accounts
     else {
         return Err(ProgramError::NotEnoughAccountKeys);
@@ -23,30 +22,72 @@ pub fn process_take_instruction(accounts: &[AccountView], _data: &[u8]) -> Progr
         return Err(ProgramError::IncorrectAuthority);
     }
 
+    let clock = Clock::get()?;
+
+    if !escrow_account.owned_by(&ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
     let escrow_data = unsafe { escrow_account.borrow_unchecked() };
     let escrow_state = ::wincode::deserialize::<Escrow>(escrow_data)
         .map_err(|_| ProgramError::InvalidAccountData)?;
+    escrow_state.check_header()?;
 
     if escrow_state.maker != *maker.address().as_array() {
         return Err(ProgramError::InvalidAccountData);
     }
 
+    escrow_state.check_taker(taker.address())?;
+
+    if mint_a.address().as_array() != &escrow_state.mint_a
+        || mint_b.address().as_array() != &escrow_state.mint_b
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if clock.unix_timestamp >= escrow_state.expires_at {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let seed = escrow_state.seed;
     let bump = escrow_state.bump;
     let amount_to_receive = escrow_state.amount_to_receive;
     let amount_to_give = escrow_state.amount_to_give;
-    let mint_b = escrow_state.mint_b;
 
-    {
-        let maker_ata_b_state = TokenAccount::from_account_view(maker_ata_b)?;
-        if maker_ata_b_state.owner() != maker.address() {
-            return Err(ProgramError::IllegalOwner);
-        }
-        if maker_ata_b_state.mint().as_array() != &mint_b {
-            return Err(ProgramError::InvalidAccountData);
-        }
+    token::check_token_account(maker_ata_b, mint_b, Some(maker.address()))?;
+    token::check_token_account(taker_ata_a, mint_a, None)?;
+    token::check_token_account(taker_ata_b, mint_b, Some(taker.address()))?;
+    token::check_token_account(escrow_ata, mint_a, Some(escrow_account.address()))?;
+
+    token::check_token_program(token_program_a, mint_a)?;
+    token::check_token_program(token_program_b, mint_b)?;
+
+    if config_account.address() != &Config::ADDRESS {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // No fee is charged until the config has been created.
+    let (fee, fee_recipient) = if config_account.owned_by(&ID) {
+        let config_data = unsafe { config_account.borrow_unchecked() };
+        let config = ::wincode::deserialize::<Config>(config_data)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        config.check_header()?;
+
+        (config.fee_for(amount_to_receive), config.fee_recipient)
+    } else {
+        (0, [0; 32])
+    };
+
+    if fee > 0 {
+        token::check_token_account(
+            fee_ata_b,
+            mint_b,
+            Some(&Address::new_from_array(fee_recipient)),
+        )?;
     }
 
-    let seeds: [&[u8]; 3] = [b"escrow", maker.address().as_array(), &[bump]];
+    let seed_bytes = seed.to_le_bytes();
+    let seeds: [&[u8]; 4] = [b"escrow", maker.address().as_array(), &seed_bytes, &[bump]];
     let expected_escrow = derive_address(&seeds, None, ID.as_array());
 
     if escrow_account.address().as_array() != &expected_escrow {
@@ -57,35 +98,71 @@ pub fn process_take_instruction(accounts: &[AccountView], _data: &[u8]) -> Progr
     let signer_seeds = [
         Seed::from(b"escrow"),
         Seed::from(maker.address().as_array()),
+        Seed::from(&seed_bytes),
         Seed::from(&bump_seed),
     ];
 
-    Transfer {
-        from: taker_ata_b,
-        to: maker_ata_b,
-        authority: taker,
-        amount: amount_to_receive,
-    }
-    .invoke()?;
+    // The protocol fee comes out of the maker's proceeds. Both legs are
+    // grossed up by any transfer fee, so the maker and the fee recipient are
+    // credited exactly their share and the taker pays transfer fees on top.
+    let decimals_b = token::mint_decimals(mint_b)?;
 
-    Transfer {
-        from: escrow_ata,
-        to: taker_ata_a,
-        authority: escrow_account,
-        amount: amount_to_give,
+    if fee > 0 {
+        token::transfer_checked(
+            token_program_b,
+            taker_ata_b,
+            mint_b,
+            fee_ata_b,
+            taker,
+            token::amount_with_fee(mint_b, fee, clock.epoch)?,
+            decimals_b,
+            &[],
+        )?;
     }
-    .invoke_signed(&[Signer::from(&signer_seeds[..])])?;
 
-    CloseAccount {
-        account: escrow_ata,
-        destination: maker,
-        authority: escrow_account,
-    }
-    .invoke_signed(&[Signer::from(&signer_seeds[..])])?;
+    token::transfer_checked(
+        token_program_b,
+        taker_ata_b,
+        mint_b,
+        maker_ata_b,
+        taker,
+        token::amount_with_fee(mint_b, amount_to_receive - fee, clock.epoch)?,
+        decimals_b,
+        &[],
+    )?;
+
+    token::transfer_checked(
+        token_program_a,
+        escrow_ata,
+        mint_a,
+        taker_ata_a,
+        escrow_account,
+        amount_to_give,
+        token::mint_decimals(mint_a)?,
+        &[Signer::from(&signer_seeds[..])],
+    )?;
+
+    token::close_vault(
+        token_program_a,
+        escrow_ata,
+        mint_a,
+        maker,
+        escrow_account,
+        &[Signer::from(&signer_seeds[..])],
+    )?;
+
+    Escrow::close(escrow_account, maker)?;
 
-    let escrow_lamports = escrow_account.lamports();
-    maker.set_lamports(maker.lamports() + escrow_lamports);
-    escrow_account.set_lamports(0);
+    events::taken(
+        escrow_account,
+        maker,
+        taker,
+        mint_a,
+        mint_b,
+        amount_to_receive,
+        amount_to_give,
+        fee,
+    );
 
     Ok(())
 }
@@ -0,0 +1,72 @@
+use ::wincode::SchemaRead;
+use pinocchio::{error::ProgramError, AccountView, ProgramResult};
+
+use super::process_take_instruction;
+
+/// Accounts each escrow adds after the shared ones: maker, escrow, maker_ata_b
+/// and vault.
+const ESCROW_ACCOUNTS: usize = 4;
+
+#[derive(SchemaRead)]
+pub struct TakeManyInstructionData {
+    pub count: u8,
+}
+
+/// Takes several offers for the same pair at once. Each escrow is settled by
+/// `take` with the shared taker, mint, program and fee accounts, so it gets the
+/// same checks; any failure fails the whole instruction, and with it every
+/// settlement already made.
+pub fn process_take_many_instruction(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let [taker, taker_ata_a, taker_ata_b, token_program_a, mint_a, mint_b, token_program_b, config_account, fee_ata_b, remaining @ ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let ix_data = ::wincode::deserialize::<TakeManyInstructionData>(data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let count = ix_data.count as usize;
+
+    if count == 0 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if remaining.len() != count * ESCROW_ACCOUNTS {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    for (index, group) in remaining.chunks_exact(ESCROW_ACCOUNTS).enumerate() {
+        let [maker, escrow_account, maker_ata_b, escrow_ata] = group else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        // An escrow already settled earlier in the batch.
+        if remaining[..index * ESCROW_ACCOUNTS]
+            .chunks_exact(ESCROW_ACCOUNTS)
+            .any(|taken| taken[1].address() == escrow_account.address())
+        {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        process_take_instruction(
+            &[
+                taker.clone(),
+                maker.clone(),
+                escrow_account.clone(),
+                taker_ata_a.clone(),
+                taker_ata_b.clone(),
+                maker_ata_b.clone(),
+                escrow_ata.clone(),
+                token_program_a.clone(),
+                mint_a.clone(),
+                mint_b.clone(),
+                token_program_b.clone(),
+                config_account.clone(),
+                fee_ata_b.clone(),
+            ],
+            &[],
+        )?;
+    }
+
+    Ok(())
+}
@@ -1,9 +1,13 @@
 #![allow(unexpected_cfgs)]
 
+#[cfg(feature = "cpi")]
+pub mod cpi;
 mod entrypoint;
+mod events;
 mod instructions;
 mod state;
 mod tests;
+mod token;
 mod utils;
 
-pub use entrypoint::ID;
+pub use entrypoint::{process_instruction, ID};
@@ -0,0 +1,76 @@
+use crate::utils::{impl_len, impl_load};
+use pinocchio::{error::ProgramError, AccountView, Address};
+use wincode::SchemaRead;
+
+const BPF_LOADER_UPGRADEABLE: Address =
+    Address::from_str_const("BPFLoaderUpgradeab1e11111111111111111111111");
+
+/// Protocol-wide fee settings, stored at the single `["config"]` PDA.
+#[repr(C)]
+#[derive(Clone, Copy, SchemaRead)]
+pub struct Config {
+    pub discriminator: u8,
+    pub version: u8,
+    pub bump: u8,
+    pub _padding: [u8; 3],
+    pub fee_basis_points: u16,
+    pub admin: [u8; 32],
+    pub fee_recipient: [u8; 32],
+}
+
+impl_len!(Config);
+impl_load!(Config);
+
+impl Config {
+    pub const DISCRIMINATOR: u8 = 2;
+    pub const VERSION: u8 = 1;
+    /// `["config"]` derived off-chain for this program id. Fixing it here lets
+    /// `take` check the account with one comparison and rules out configs at
+    /// non-canonical bumps.
+    pub const ADDRESS: Address =
+        Address::from_str_const("B8KH1w8CpHCw7vmUWR1DGPTziwixTN2x1DRyqpTGswYq");
+    pub const BUMP: u8 = 255;
+    /// This program's `ProgramData` account under the upgradeable loader,
+    /// derived off-chain like `ADDRESS`. Its upgrade authority is the only key
+    /// that may create the config.
+    pub const PROGRAM_DATA: Address =
+        Address::from_str_const("EDjBifMQpfHADU9Dxdi1M7dLtJEDmfrqojVuFkdcDA6H");
+    /// 10%. Caps what a compromised or careless admin can skim off a take.
+    pub const MAX_FEE_BASIS_POINTS: u16 = 1_000;
+
+    pub fn check_header(&self) -> Result<(), ProgramError> {
+        if self.discriminator != Self::DISCRIMINATOR || self.version != Self::VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
+
+    /// Protocol fee on a payment of `amount`, rounded down.
+    pub fn fee_for(&self, amount: u64) -> u64 {
+        (amount as u128 * self.fee_basis_points as u128 / 10_000) as u64
+    }
+
+    /// Fails unless `program_data` is [`Self::PROGRAM_DATA`] and names
+    /// `authority` as the program's upgrade authority. A program deployed as
+    /// immutable has none, so its config can never be created.
+    pub fn check_upgrade_authority(
+        program_data: &AccountView,
+        authority: &AccountView,
+    ) -> Result<(), ProgramError> {
+        if program_data.address() != &Self::PROGRAM_DATA
+            || !program_data.owned_by(&BPF_LOADER_UPGRADEABLE)
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // [state:4 = 3 (ProgramData)][slot:8][has_authority:1][authority:32]
+        let data = unsafe { program_data.borrow_unchecked() };
+        if data.len() < 45 || data[0..4] != [3, 0, 0, 0] {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if data[12] != 1 || data[13..45] != *authority.address().as_array() {
+            return Err(ProgramError::IncorrectAuthority);
+        }
+        Ok(())
+    }
+}
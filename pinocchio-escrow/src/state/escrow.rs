@@ -1,17 +1,113 @@
 use crate::utils::{impl_len, impl_load};
+use pinocchio::{error::ProgramError, AccountView, Address, ProgramResult};
 use wincode::SchemaRead;
 
 #[repr(C)]
 #[derive(Clone, Copy, SchemaRead)]
 pub struct Escrow {
+    pub discriminator: u8,
+    pub version: u8,
+    pub bump: u8,
+    pub _padding: [u8; 5],
     pub maker: [u8; 32],
     pub mint_a: [u8; 32],
     pub mint_b: [u8; 32],
+    pub taker: [u8; 32],
+    pub seed: u64,
     pub amount_to_receive: u64,
     pub amount_to_give: u64,
-    pub bump: u8,
-    pub _padding: [u8; 7],
+    pub expires_at: i64,
 }
 
 impl_len!(Escrow);
 impl_load!(Escrow);
+
+impl Escrow {
+    /// First byte of every escrow account, so no other account of the same
+    /// size can be passed off as one.
+    pub const DISCRIMINATOR: u8 = 1;
+    /// Layout version written by `make`. Bump it when the layout changes.
+    pub const VERSION: u8 = 2;
+
+    /// Fails unless the header marks this as an escrow in the current layout.
+    pub fn check_header(&self) -> Result<(), ProgramError> {
+        if self.discriminator != Self::DISCRIMINATOR || self.version != Self::VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
+
+    /// Closes `escrow_account` and pays its rent to `maker`. Draining the
+    /// lamports alone leaves the data readable until the end of the
+    /// transaction, so a transfer back into the account by a later instruction
+    /// would revive the offer. Cutting the data to zero bytes and handing the
+    /// account back to the system program rules that out.
+    pub fn close(escrow_account: &AccountView, maker: &AccountView) -> ProgramResult {
+        maker.set_lamports(maker.lamports() + escrow_account.lamports());
+        escrow_account.set_lamports(0);
+        escrow_account.resize(0)?;
+        unsafe { escrow_account.assign(&pinocchio_system::ID) };
+        Ok(())
+    }
+
+    /// Fails unless `taker` may fill this offer: anyone when no taker was
+    /// designated, otherwise only the designated one.
+    pub fn check_taker(&self, taker: &Address) -> Result<(), ProgramError> {
+        if self.taker != [0; 32] && self.taker != *taker.as_array() {
+            return Err(ProgramError::IncorrectAuthority);
+        }
+        Ok(())
+    }
+
+    /// Amount of `mint_a` released to a taker who pays `fill` of the remaining
+    /// `amount_to_receive`. Rounds down so the maker never gives more than the
+    /// offered rate; filling the whole remainder releases everything left.
+    pub fn amount_for_fill(&self, fill: u64) -> Result<u64, ProgramError> {
+        if fill == 0 || fill > self.amount_to_receive {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        if fill == self.amount_to_receive {
+            return Ok(self.amount_to_give);
+        }
+
+        let amount =
+            (fill as u128 * self.amount_to_give as u128 / self.amount_to_receive as u128) as u64;
+        if amount == 0 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(amount)
+    }
+}
+
+/// Escrow layout before offers could name a taker. `migrate` is the only
+/// instruction that reads it; every other one requires the current layout.
+#[repr(C)]
+#[derive(Clone, Copy, SchemaRead)]
+pub struct EscrowV1 {
+    pub discriminator: u8,
+    pub version: u8,
+    pub bump: u8,
+    pub _padding: [u8; 5],
+    pub maker: [u8; 32],
+    pub mint_a: [u8; 32],
+    pub mint_b: [u8; 32],
+    pub seed: u64,
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+    pub expires_at: i64,
+}
+
+impl_len!(EscrowV1);
+impl_load!(EscrowV1);
+
+impl EscrowV1 {
+    pub const VERSION: u8 = 1;
+
+    /// Fails unless the header marks this as an escrow in the version 1 layout.
+    pub fn check_header(&self) -> Result<(), ProgramError> {
+        if self.discriminator != Escrow::DISCRIMINATOR || self.version != Self::VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
+}
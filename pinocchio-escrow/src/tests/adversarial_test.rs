@@ -0,0 +1,209 @@
+use escrow_client::{escrow_address, vault_address, Variant, TOKEN_PROGRAM_ID};
+use litesvm_token::{CreateAssociatedTokenAccount, CreateMint, MintTo};
+use solana_instruction::{AccountMeta, Instruction};
+use solana_keypair::Keypair;
+use solana_message::Message;
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use super::helpers::{
+    cancel, send_as_maker, send_as_taker, send_cancel, send_make, send_take, setup_make_with,
+    setup_taker, take, token_amount, MakeSetup, Offer, DURATION,
+};
+
+const AMOUNT_TO_RECEIVE: u64 = 100_000_000;
+const AMOUNT_TO_GIVE: u64 = 500_000_000;
+
+fn setup(variant: Variant) -> MakeSetup {
+    setup_make_with(variant, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE)
+}
+
+#[test]
+fn test_spoofed_escrow_rejected() {
+    for v in Variant::ALL {
+        let mut s = setup(v);
+        let t = setup_taker(&mut s, AMOUNT_TO_RECEIVE);
+        let genuine = s.svm.get_account(&s.escrow_pda).unwrap();
+
+        // Same bytes, but owned by another program.
+        let mut foreign = genuine.clone();
+        foreign.owner = Pubkey::new_unique();
+        // Owned by the escrow program, but without the escrow discriminator.
+        let mut untagged = genuine.clone();
+        untagged.data[0] = 0;
+        // A layout version this build does not know.
+        let mut future = genuine.clone();
+        future.data[1] += 1;
+
+        for spoof in [foreign, untagged, future] {
+            s.svm.set_account(s.escrow_pda, spoof).unwrap();
+            assert!(send_take(&mut s, &t, v).is_err());
+            assert!(send_cancel(&mut s, v).is_err());
+        }
+
+        s.svm.set_account(s.escrow_pda, genuine).unwrap();
+        send_take(&mut s, &t, v).unwrap();
+    }
+}
+
+#[test]
+fn test_wrong_mint_rejected() {
+    for v in Variant::ALL {
+        let mut s = setup(v);
+        let t = setup_taker(&mut s, AMOUNT_TO_RECEIVE);
+
+        let mint_c = CreateMint::new(&mut s.svm, &s.maker)
+            .decimals(6)
+            .authority(&s.maker.pubkey())
+            .send()
+            .unwrap();
+        let taker_ata_c = CreateAssociatedTokenAccount::new(&mut s.svm, &t.taker, &mint_c)
+            .owner(&t.taker.pubkey())
+            .send()
+            .unwrap();
+        let maker_ata_c = CreateAssociatedTokenAccount::new(&mut s.svm, &t.taker, &mint_c)
+            .owner(&s.maker.pubkey())
+            .send()
+            .unwrap();
+        MintTo::new(
+            &mut s.svm,
+            &s.maker,
+            &mint_c,
+            &taker_ata_c,
+            AMOUNT_TO_RECEIVE,
+        )
+        .send()
+        .unwrap();
+
+        let genuine = take(&s, &t);
+
+        // Pays in mint_c instead of the mint_b the maker asked for.
+        let mut pay_in_c = genuine;
+        pay_in_c.taker_ata_b = taker_ata_c;
+        pay_in_c.maker_ata_b = maker_ata_c;
+        pay_in_c.mint_b = mint_c;
+        // Claims the offer is for mint_c. Set on the built instruction, since
+        // the builder would also derive the vault from it.
+        let mut claim_c = genuine.instruction(v);
+        claim_c.accounts[8] = AccountMeta::new(mint_c, false);
+        // Right mints, but the taker's accounts hold mint_c.
+        let mut receive_in_c = genuine;
+        receive_in_c.taker_ata_a = taker_ata_c;
+        let mut send_from_c = genuine;
+        send_from_c.taker_ata_b = taker_ata_c;
+
+        for ix in [
+            pay_in_c.instruction(v),
+            claim_c,
+            receive_in_c.instruction(v),
+            send_from_c.instruction(v),
+        ] {
+            assert!(send_as_taker(&mut s, &t, ix).is_err());
+        }
+
+        send_take(&mut s, &t, v).unwrap();
+        assert_eq!(token_amount(&s.svm, &t.maker_ata_b), AMOUNT_TO_RECEIVE);
+    }
+}
+
+#[test]
+fn test_substituted_vault_rejected() {
+    for v in Variant::ALL {
+        let mut s = setup(v);
+        let t = setup_taker(&mut s, AMOUNT_TO_RECEIVE);
+
+        // A second offer from the same maker, with its own vault.
+        MintTo::new(
+            &mut s.svm,
+            &s.maker,
+            &s.mint_a,
+            &s.maker_ata_a,
+            AMOUNT_TO_GIVE,
+        )
+        .send()
+        .unwrap();
+        let offer = Offer {
+            seed: 1,
+            amount_to_receive: AMOUNT_TO_RECEIVE,
+            amount_to_give: AMOUNT_TO_GIVE,
+            duration: DURATION,
+            taker: None,
+        };
+        let (mint_a, mint_b, maker_ata_a) = (s.mint_a, s.mint_b, s.maker_ata_a);
+        send_make(
+            &mut s.svm,
+            &s.maker,
+            &mint_a,
+            &mint_b,
+            &maker_ata_a,
+            v,
+            offer,
+        )
+        .unwrap();
+        let (other_escrow, _) = escrow_address(&s.maker.pubkey(), offer.seed);
+        let other_vault = vault_address(&other_escrow, &mint_a, &TOKEN_PROGRAM_ID);
+
+        for vault in [other_vault, t.taker_ata_a] {
+            let mut ix = take(&s, &t).instruction(v);
+            ix.accounts[6] = AccountMeta::new(vault, false);
+            assert!(send_as_taker(&mut s, &t, ix).is_err());
+
+            let mut ix = cancel(&s).instruction(v);
+            ix.accounts[3] = AccountMeta::new(vault, false);
+            assert!(send_as_maker(&mut s, ix).is_err());
+        }
+
+        send_take(&mut s, &t, v).unwrap();
+        assert_eq!(token_amount(&s.svm, &t.taker_ata_a), AMOUNT_TO_GIVE);
+        assert_eq!(token_amount(&s.svm, &other_vault), AMOUNT_TO_GIVE);
+    }
+}
+
+/// System program transfer of `lamports` from `from` to `to`.
+fn transfer(from: &Pubkey, to: &Pubkey, lamports: u64) -> Instruction {
+    let mut data = 2u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&lamports.to_le_bytes());
+    Instruction {
+        program_id: solana_sdk_ids::system_program::ID,
+        accounts: vec![AccountMeta::new(*from, true), AccountMeta::new(*to, false)],
+        data,
+    }
+}
+
+/// Sends `close` and then a transfer of the escrow's rent straight back into
+/// it, in one transaction signed by `signer`.
+fn close_and_refund(s: &mut MakeSetup, signer: &Keypair, close: Instruction) {
+    let rent = s.svm.get_account(&s.escrow_pda).unwrap().lamports;
+    let refund = transfer(&signer.pubkey(), &s.escrow_pda, rent);
+
+    s.svm.expire_blockhash();
+    let msg = Message::new(&[close, refund], Some(&signer.pubkey()));
+    let blockhash = s.svm.latest_blockhash();
+    s.svm
+        .send_transaction(Transaction::new(&[signer], msg, blockhash))
+        .unwrap();
+
+    // The lamports land in a plain system account, not a revived escrow.
+    let account = s.svm.get_account(&s.escrow_pda).unwrap();
+    assert_eq!(account.owner, solana_sdk_ids::system_program::ID);
+    assert!(account.data.is_empty());
+}
+
+#[test]
+fn test_closed_escrow_cannot_be_revived() {
+    for v in Variant::ALL {
+        let mut s = setup(v);
+        let maker = s.maker.insecure_clone();
+        let ix = cancel(&s).instruction(v);
+        close_and_refund(&mut s, &maker, ix);
+        assert!(send_cancel(&mut s, v).is_err());
+
+        let mut s = setup(v);
+        let t = setup_taker(&mut s, AMOUNT_TO_RECEIVE);
+        let ix = take(&s, &t).instruction(v);
+        close_and_refund(&mut s, &t.taker, ix);
+        assert!(send_take(&mut s, &t, v).is_err());
+        assert!(send_cancel(&mut s, v).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use escrow_client::{
+    config_address, escrow_address, program_data_address, Config, DecodeError, Escrow, Variant,
+};
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+
+use super::helpers::{load_config, load_escrow, set_fee, setup_make, DURATION};
+use crate::state::{Config as ConfigState, Escrow as EscrowState};
+
+#[test]
+fn test_client_matches_program() {
+    assert_eq!(escrow_client::ID, Pubkey::from(crate::ID));
+    assert_eq!(Escrow::LEN, EscrowState::LEN);
+    assert_eq!(Escrow::DISCRIMINATOR, EscrowState::DISCRIMINATOR);
+    assert_eq!(Escrow::VERSION, EscrowState::VERSION);
+
+    // The program hardcodes the config PDA instead of deriving it.
+    assert_eq!(
+        config_address(),
+        (Pubkey::from(ConfigState::ADDRESS), ConfigState::BUMP)
+    );
+    assert_eq!(Config::LEN, ConfigState::LEN);
+    assert_eq!(Config::DISCRIMINATOR, ConfigState::DISCRIMINATOR);
+    assert_eq!(Config::VERSION, ConfigState::VERSION);
+    assert_eq!(
+        program_data_address(),
+        Pubkey::from(ConfigState::PROGRAM_DATA)
+    );
+    assert_eq!(
+        Config::MAX_FEE_BASIS_POINTS,
+        ConfigState::MAX_FEE_BASIS_POINTS
+    );
+}
+
+#[test]
+fn test_decode_config() {
+    let mut s = setup_make(100_000_000, 500_000_000);
+    set_fee(&mut s, Variant::Unsafe, 250);
+    let config = load_config(&s.svm);
+
+    assert_eq!(config.bump, ConfigState::BUMP);
+    assert_eq!(config.admin, s.maker.pubkey());
+    assert_ne!(config.fee_recipient, s.maker.pubkey());
+    assert_eq!(config.fee_basis_points, 250);
+}
+
+#[test]
+fn test_decode_escrow() {
+    let s = setup_make(100_000_000, 500_000_000);
+    let escrow = load_escrow(&s.svm, &s.escrow_pda);
+    let (_, bump) = escrow_address(&s.maker.pubkey(), s.seed);
+
+    assert_eq!(escrow.bump, bump);
+    assert_eq!(escrow.maker, s.maker.pubkey());
+    assert_eq!(escrow.mint_a, s.mint_a);
+    assert_eq!(escrow.mint_b, s.mint_b);
+    assert_eq!(escrow.taker, None);
+    assert_eq!(escrow.seed, s.seed);
+    assert_eq!(escrow.amount_to_receive, 100_000_000);
+    assert_eq!(escrow.amount_to_give, 500_000_000);
+    assert!(escrow.expires_at >= DURATION);
+
+    let mut data = s.svm.get_account(&s.escrow_pda).unwrap().data;
+    assert_eq!(
+        Escrow::decode(&data[1..]),
+        Err(DecodeError::InvalidLength(Escrow::LEN - 1))
+    );
+    data[1] += 1;
+    assert_eq!(
+        Escrow::decode(&data),
+        Err(DecodeError::UnsupportedVersion(Escrow::VERSION + 1))
+    );
+    data[0] = 0;
+    assert_eq!(
+        Escrow::decode(&data),
+        Err(DecodeError::InvalidDiscriminator(0))
+    );
+}
@@ -0,0 +1,199 @@
+use std::path::PathBuf;
+
+use escrow_client::{escrow_address, vault_address, Make, Take, Variant, TOKEN_PROGRAM_ID};
+use litesvm::{types::TransactionResult, LiteSVM};
+use litesvm_token::{CreateAssociatedTokenAccount, CreateMint, MintTo};
+use solana_instruction::{AccountMeta, Instruction};
+use solana_keypair::Keypair;
+use solana_message::Message;
+use solana_native_token::LAMPORTS_PER_SOL;
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use super::helpers::{
+    fee_ata_b, lamports, load_escrow, load_svm, send_as_maker, setup_make_with, token_amount,
+    DURATION,
+};
+
+const PDA_MAKER_ID: Pubkey = Pubkey::from_str_const("EpT4bLcdy1jKbUvGv5HqP9bXmLiyki9n3JrtV1pSJMLG");
+
+const AMOUNT_TO_RECEIVE: u64 = 100_000_000;
+const AMOUNT_TO_GIVE: u64 = 500_000_000;
+
+/// Adds the `pda-maker` program and funds its authority PDA, returning the
+/// authority and its bump.
+fn load_pda_maker(svm: &mut LiteSVM) -> (Pubkey, u8) {
+    let so_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("pda-maker/target/sbpf-solana-solana/release/pda_maker.so");
+
+    let program_data = std::fs::read(so_path).expect("Failed to read pda-maker SO file");
+    svm.add_program(PDA_MAKER_ID, &program_data).unwrap();
+
+    let (authority, bump) = Pubkey::find_program_address(&[b"authority"], &PDA_MAKER_ID);
+    svm.airdrop(&authority, LAMPORTS_PER_SOL).unwrap();
+    (authority, bump)
+}
+
+/// Turns `ix`, an escrow instruction with the authority as its first account,
+/// into the `pda-maker` instruction that sends it on behalf of the authority.
+fn through_pda_maker(ix: Instruction, bump: u8) -> Instruction {
+    let mut accounts = ix.accounts;
+    accounts[0].is_signer = false;
+    accounts.push(AccountMeta::new_readonly(escrow_client::ID, false));
+
+    let mut data = vec![ix.data[0], bump];
+    data.extend_from_slice(&ix.data[1..]);
+
+    Instruction {
+        program_id: PDA_MAKER_ID,
+        accounts,
+        data,
+    }
+}
+
+fn send(svm: &mut LiteSVM, payer: &Keypair, ix: Instruction) -> TransactionResult {
+    let msg = Message::new(&[ix], Some(&payer.pubkey()));
+    let blockhash = svm.latest_blockhash();
+    svm.send_transaction(Transaction::new(&[payer], msg, blockhash))
+}
+
+#[test]
+fn test_cpi_make() {
+    let (mut svm, payer) = load_svm();
+    let (authority, bump) = load_pda_maker(&mut svm);
+
+    let mint_a = CreateMint::new(&mut svm, &payer)
+        .decimals(6)
+        .authority(&payer.pubkey())
+        .send()
+        .unwrap();
+    let mint_b = CreateMint::new(&mut svm, &payer)
+        .decimals(6)
+        .authority(&payer.pubkey())
+        .send()
+        .unwrap();
+    let authority_ata_a = CreateAssociatedTokenAccount::new(&mut svm, &payer, &mint_a)
+        .owner(&authority)
+        .send()
+        .unwrap();
+    let authority_ata_b = CreateAssociatedTokenAccount::new(&mut svm, &payer, &mint_b)
+        .owner(&authority)
+        .send()
+        .unwrap();
+    MintTo::new(&mut svm, &payer, &mint_a, &authority_ata_a, AMOUNT_TO_GIVE)
+        .send()
+        .unwrap();
+
+    let make = Make {
+        maker: authority,
+        mint_a,
+        mint_b,
+        maker_ata_a: authority_ata_a,
+        token_program: TOKEN_PROGRAM_ID,
+        seed: 7,
+        amount_to_receive: AMOUNT_TO_RECEIVE,
+        amount_to_give: AMOUNT_TO_GIVE,
+        duration: DURATION,
+        taker: None,
+    }
+    .instruction(Variant::Unsafe);
+
+    // Nobody can sign for the PDA outside its program.
+    let mut unsigned = make.clone();
+    unsigned.accounts[0].is_signer = false;
+    assert!(send(&mut svm, &payer, unsigned).is_err());
+
+    send(&mut svm, &payer, through_pda_maker(make, bump)).unwrap();
+
+    let (escrow_pda, _) = escrow_address(&authority, 7);
+    let escrow_ata = vault_address(&escrow_pda, &mint_a, &TOKEN_PROGRAM_ID);
+    let escrow = load_escrow(&svm, &escrow_pda);
+    assert_eq!(escrow.maker, authority);
+    assert_eq!(escrow.amount_to_give, AMOUNT_TO_GIVE);
+    assert_eq!(token_amount(&svm, &escrow_ata), AMOUNT_TO_GIVE);
+
+    // The PDA's offer is taken like any other.
+    let taker = Keypair::new();
+    svm.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+    let taker_ata_a = CreateAssociatedTokenAccount::new(&mut svm, &taker, &mint_a)
+        .owner(&taker.pubkey())
+        .send()
+        .unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut svm, &taker, &mint_b)
+        .owner(&taker.pubkey())
+        .send()
+        .unwrap();
+    MintTo::new(&mut svm, &payer, &mint_b, &taker_ata_b, AMOUNT_TO_RECEIVE)
+        .send()
+        .unwrap();
+
+    let ix = Take {
+        taker: taker.pubkey(),
+        maker: authority,
+        seed: 7,
+        mint_a,
+        mint_b,
+        taker_ata_a,
+        taker_ata_b,
+        maker_ata_b: authority_ata_b,
+        token_program_a: TOKEN_PROGRAM_ID,
+        token_program_b: TOKEN_PROGRAM_ID,
+        fee_ata_b: fee_ata_b(&svm, &mint_b),
+    }
+    .instruction(Variant::Unsafe);
+    send(&mut svm, &taker, ix).unwrap();
+
+    assert_eq!(token_amount(&svm, &authority_ata_b), AMOUNT_TO_RECEIVE);
+    assert_eq!(token_amount(&svm, &taker_ata_a), AMOUNT_TO_GIVE);
+    assert_eq!(lamports(&svm, &escrow_pda), 0);
+}
+
+#[test]
+fn test_cpi_take() {
+    let mut s = setup_make_with(Variant::Unsafe, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE);
+    let (authority, bump) = load_pda_maker(&mut s.svm);
+
+    let authority_ata_a = CreateAssociatedTokenAccount::new(&mut s.svm, &s.maker, &s.mint_a)
+        .owner(&authority)
+        .send()
+        .unwrap();
+    let authority_ata_b = CreateAssociatedTokenAccount::new(&mut s.svm, &s.maker, &s.mint_b)
+        .owner(&authority)
+        .send()
+        .unwrap();
+    let maker_ata_b = CreateAssociatedTokenAccount::new(&mut s.svm, &s.maker, &s.mint_b)
+        .owner(&s.maker.pubkey())
+        .send()
+        .unwrap();
+    MintTo::new(
+        &mut s.svm,
+        &s.maker,
+        &s.mint_b,
+        &authority_ata_b,
+        AMOUNT_TO_RECEIVE,
+    )
+    .send()
+    .unwrap();
+
+    let take = Take {
+        taker: authority,
+        maker: s.maker.pubkey(),
+        seed: s.seed,
+        mint_a: s.mint_a,
+        mint_b: s.mint_b,
+        taker_ata_a: authority_ata_a,
+        taker_ata_b: authority_ata_b,
+        maker_ata_b,
+        token_program_a: TOKEN_PROGRAM_ID,
+        token_program_b: TOKEN_PROGRAM_ID,
+        fee_ata_b: fee_ata_b(&s.svm, &s.mint_b),
+    }
+    .instruction(Variant::Unsafe);
+    send_as_maker(&mut s, through_pda_maker(take, bump)).unwrap();
+
+    assert_eq!(token_amount(&s.svm, &authority_ata_a), AMOUNT_TO_GIVE);
+    assert_eq!(token_amount(&s.svm, &authority_ata_b), 0);
+    assert_eq!(token_amount(&s.svm, &maker_ata_b), AMOUNT_TO_RECEIVE);
+    assert_eq!(lamports(&s.svm, &s.escrow_pda), 0);
+}
@@ -1,80 +1,34 @@
-use litesvm_token::{CreateAssociatedTokenAccount, MintTo};
-use solana_instruction::{AccountMeta, Instruction};
-use solana_keypair::Keypair;
-use solana_message::Message;
-use solana_native_token::LAMPORTS_PER_SOL;
-use solana_signer::Signer;
-use solana_transaction::Transaction;
-
-use super::helpers::{program_id, setup_make, setup_make_v2, MakeSetup, TOKEN_PROGRAM_ID};
-
-fn do_take(s: &mut MakeSetup, disc: u8, amount_to_receive: u64) -> u64 {
-    let taker = Keypair::new();
-    s.svm
-        .airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL)
-        .unwrap();
-
-    let taker_ata_a = CreateAssociatedTokenAccount::new(&mut s.svm, &taker, &s.mint_a)
-        .owner(&taker.pubkey())
-        .send()
-        .unwrap();
-    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut s.svm, &taker, &s.mint_b)
-        .owner(&taker.pubkey())
-        .send()
-        .unwrap();
-    let maker_ata_b = CreateAssociatedTokenAccount::new(&mut s.svm, &taker, &s.mint_b)
-        .owner(&s.maker.pubkey())
-        .send()
-        .unwrap();
-
-    MintTo::new(
-        &mut s.svm,
-        &s.maker,
-        &s.mint_b,
-        &taker_ata_b,
-        amount_to_receive,
-    )
-    .send()
-    .unwrap();
-
-    let ix = Instruction {
-        program_id: program_id(),
-        accounts: vec![
-            AccountMeta::new(taker.pubkey(), true),
-            AccountMeta::new(s.maker.pubkey(), false),
-            AccountMeta::new(s.escrow_pda, false),
-            AccountMeta::new(taker_ata_a, false),
-            AccountMeta::new(taker_ata_b, false),
-            AccountMeta::new(maker_ata_b, false),
-            AccountMeta::new(s.escrow_ata, false),
-            AccountMeta::new(TOKEN_PROGRAM_ID, false),
-        ],
-        data: vec![disc],
-    };
-
-    let msg = Message::new(&[ix], Some(&taker.pubkey()));
-    let blockhash = s.svm.latest_blockhash();
-    let tx = Transaction::new(&[&taker], msg, blockhash);
-    s.svm.send_transaction(tx).unwrap().compute_units_consumed
+use escrow_client::Variant;
+
+use super::helpers::{
+    send_cancel, send_take, send_take_many, send_take_partial, set_fee, setup_make,
+    setup_make_many, setup_make_v2, setup_taker, MakeSetup,
+};
+
+fn do_take(s: &mut MakeSetup, variant: Variant, amount_to_receive: u64) -> u64 {
+    let t = setup_taker(s, amount_to_receive);
+    send_take(s, &t, variant).unwrap().compute_units_consumed
+}
+
+fn do_cancel(s: &mut MakeSetup, variant: Variant) -> u64 {
+    send_cancel(s, variant).unwrap().compute_units_consumed
+}
+
+fn do_take_partial(s: &mut MakeSetup, variant: Variant, fill: u64) -> u64 {
+    let t = setup_taker(s, fill);
+    send_take_partial(s, &t, variant, fill)
+        .unwrap()
+        .compute_units_consumed
 }
 
-fn do_cancel(s: &mut MakeSetup, disc: u8) -> u64 {
-    let ix = Instruction {
-        program_id: program_id(),
-        accounts: vec![
-            AccountMeta::new(s.maker.pubkey(), true),
-            AccountMeta::new(s.escrow_pda, false),
-            AccountMeta::new(s.maker_ata_a, false),
-            AccountMeta::new(s.escrow_ata, false),
-            AccountMeta::new(TOKEN_PROGRAM_ID, false),
-        ],
-        data: vec![disc],
-    };
-
-    let msg = Message::new(&[ix], Some(&s.maker.pubkey()));
-    let blockhash = s.svm.latest_blockhash();
-    let tx = Transaction::new(&[&s.maker], msg, blockhash);
-    s.svm.send_transaction(tx).unwrap().compute_units_consumed
+/// Sweeps `count` offers of `amount_to_receive` each with one `take_many`.
+fn do_take_many(variant: Variant, count: u64, amount_to_receive: u64, amount_to_give: u64) -> u64 {
+    let mut s = setup_make_many(variant, count, amount_to_receive, amount_to_give);
+    let t = setup_taker(&mut s, count * amount_to_receive);
+    let seeds: Vec<u64> = (0..count).collect();
+    send_take_many(&mut s, &t, variant, &seeds)
+        .unwrap()
+        .compute_units_consumed
 }
 
 #[test]
@@ -84,45 +38,104 @@ fn test_cu_table() {
 
     let mut s1 = setup_make(amount_to_receive, amount_to_give);
     let make_v1 = s1.make_cu;
-    let take_v1 = do_take(&mut s1, 1, amount_to_receive);
+    let take_v1 = do_take(&mut s1, Variant::Unsafe, amount_to_receive);
 
     let mut s2 = setup_make_v2(amount_to_receive, amount_to_give);
     let make_v2 = s2.make_cu;
-    let take_v2 = do_take(&mut s2, 4, amount_to_receive);
+    let take_v2 = do_take(&mut s2, Variant::Wincode, amount_to_receive);
 
     let mut s3 = setup_make(amount_to_receive, amount_to_give);
-    let cancel_v1 = do_cancel(&mut s3, 2);
+    let cancel_v1 = do_cancel(&mut s3, Variant::Unsafe);
 
     let mut s4 = setup_make_v2(amount_to_receive, amount_to_give);
-    let cancel_v2 = do_cancel(&mut s4, 5);
+    let cancel_v2 = do_cancel(&mut s4, Variant::Wincode);
+
+    let mut s5 = setup_make(amount_to_receive, amount_to_give);
+    let partial_v1 = do_take_partial(&mut s5, Variant::Unsafe, amount_to_receive / 2);
+
+    let mut s6 = setup_make_v2(amount_to_receive, amount_to_give);
+    let partial_v2 = do_take_partial(&mut s6, Variant::Wincode, amount_to_receive / 2);
+
+    // The takes above run before any config exists, so they neither read one
+    // nor transfer a fee; these create the config and pay a 1% fee.
+    let mut s7 = setup_make(amount_to_receive, amount_to_give);
+    set_fee(&mut s7, Variant::Unsafe, 100);
+    let fee_take_v1 = do_take(&mut s7, Variant::Unsafe, amount_to_receive);
+
+    let mut s8 = setup_make_v2(amount_to_receive, amount_to_give);
+    set_fee(&mut s8, Variant::Wincode, 100);
+    let fee_take_v2 = do_take(&mut s8, Variant::Wincode, amount_to_receive);
+
+    // The cost of each escrow after the first in a batch.
+    let many_v1 = do_take_many(Variant::Unsafe, 1, amount_to_receive, amount_to_give);
+    let many_v2 = do_take_many(Variant::Wincode, 1, amount_to_receive, amount_to_give);
+    let many4_v1 = do_take_many(Variant::Unsafe, 4, amount_to_receive, amount_to_give);
+    let many4_v2 = do_take_many(Variant::Wincode, 4, amount_to_receive, amount_to_give);
+    let extra_v1 = (many4_v1 - many_v1) / 3;
+    let extra_v2 = (many4_v2 - many_v2) / 3;
 
-    let sep = "+-------------+----------+----------+-------+";
+    let sep = "+--------------+----------+----------+-------+";
     println!("{sep}");
     println!(
-        "| {:<11} | {:>8} | {:>8} | {:>5} |",
+        "| {:<12} | {:>8} | {:>8} | {:>5} |",
         "instruction", "unsafe", "wincode", "diff"
     );
     println!("{sep}");
     println!(
-        "| {:<11} | {:>8} | {:>8} | {:>+5} |",
+        "| {:<12} | {:>8} | {:>8} | {:>+5} |",
         "make",
         make_v1,
         make_v2,
         make_v2 as i64 - make_v1 as i64
     );
     println!(
-        "| {:<11} | {:>8} | {:>8} | {:>+5} |",
-        "take",
+        "| {:<12} | {:>8} | {:>8} | {:>+5} |",
+        "take no cfg",
         take_v1,
         take_v2,
         take_v2 as i64 - take_v1 as i64
     );
     println!(
-        "| {:<11} | {:>8} | {:>8} | {:>+5} |",
+        "| {:<12} | {:>8} | {:>8} | {:>+5} |",
         "cancel",
         cancel_v1,
         cancel_v2,
         cancel_v2 as i64 - cancel_v1 as i64
     );
+    println!(
+        "| {:<12} | {:>8} | {:>8} | {:>+5} |",
+        "take_partial",
+        partial_v1,
+        partial_v2,
+        partial_v2 as i64 - partial_v1 as i64
+    );
+    println!(
+        "| {:<12} | {:>8} | {:>8} | {:>+5} |",
+        "take 1% fee",
+        fee_take_v1,
+        fee_take_v2,
+        fee_take_v2 as i64 - fee_take_v1 as i64
+    );
+    println!(
+        "| {:<12} | {:>8} | {:>8} | {:>+5} |",
+        "take_many x1",
+        many_v1,
+        many_v2,
+        many_v2 as i64 - many_v1 as i64
+    );
+    println!(
+        "| {:<12} | {:>8} | {:>8} | {:>+5} |",
+        "take_many x4",
+        many4_v1,
+        many4_v2,
+        many4_v2 as i64 - many4_v1 as i64
+    );
+    println!(
+        "| {:<12} | {:>8} | {:>8} | {:>+5} |",
+        "+1 escrow",
+        extra_v1,
+        extra_v2,
+        extra_v2 as i64 - extra_v1 as i64
+    );
     println!("{sep}");
 }
@@ -0,0 +1,98 @@
+use escrow_client::Variant;
+use litesvm_token::{CreateAssociatedTokenAccount, MintTo};
+use solana_keypair::Keypair;
+use solana_native_token::LAMPORTS_PER_SOL;
+use solana_signer::Signer;
+
+use super::helpers::{
+    escrow_amounts, lamports, load_escrow, send_cancel, send_make, send_take, send_take_partial,
+    setup_make_with, setup_taker, token_amount, MakeSetup, Offer, TakerSetup, DURATION,
+};
+
+const AMOUNT_TO_RECEIVE: u64 = 100_000_000;
+const AMOUNT_TO_GIVE: u64 = 500_000_000;
+
+/// Replaces the public offer `setup_make_with` opens by one reserved for a new
+/// taker, and returns that taker along with a second, funded one.
+fn setup_private(variant: Variant) -> (MakeSetup, TakerSetup, TakerSetup) {
+    let mut s = setup_make_with(variant, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE);
+    let designated = setup_taker(&mut s, AMOUNT_TO_RECEIVE);
+
+    send_cancel(&mut s, variant).unwrap();
+    let offer = Offer {
+        seed: s.seed,
+        amount_to_receive: AMOUNT_TO_RECEIVE,
+        amount_to_give: AMOUNT_TO_GIVE,
+        duration: DURATION,
+        taker: Some(designated.taker.pubkey()),
+    };
+    send_make(
+        &mut s.svm,
+        &s.maker,
+        &s.mint_a,
+        &s.mint_b,
+        &s.maker_ata_a,
+        variant,
+        offer,
+    )
+    .unwrap();
+
+    // `setup_taker` would create the maker's `mint_b` account a second time,
+    // so the other taker shares the designated one's.
+    let taker = Keypair::new();
+    s.svm
+        .airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL)
+        .unwrap();
+    let taker_ata_a = CreateAssociatedTokenAccount::new(&mut s.svm, &taker, &s.mint_a)
+        .owner(&taker.pubkey())
+        .send()
+        .unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut s.svm, &taker, &s.mint_b)
+        .owner(&taker.pubkey())
+        .send()
+        .unwrap();
+    MintTo::new(
+        &mut s.svm,
+        &s.maker,
+        &s.mint_b,
+        &taker_ata_b,
+        AMOUNT_TO_RECEIVE,
+    )
+    .send()
+    .unwrap();
+    let other = TakerSetup {
+        taker,
+        taker_ata_a,
+        taker_ata_b,
+        maker_ata_b: designated.maker_ata_b,
+    };
+
+    (s, designated, other)
+}
+
+#[test]
+fn test_designated_taker() {
+    for variant in Variant::ALL {
+        let (mut s, designated, other) = setup_private(variant);
+        assert_eq!(
+            load_escrow(&s.svm, &s.escrow_pda).taker,
+            Some(designated.taker.pubkey())
+        );
+
+        assert!(send_take(&mut s, &other, variant).is_err());
+        assert!(send_take_partial(&mut s, &other, variant, AMOUNT_TO_RECEIVE / 2).is_err());
+        assert_eq!(
+            escrow_amounts(&s.svm, &s.escrow_pda),
+            (AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE)
+        );
+        assert_eq!(token_amount(&s.svm, &other.taker_ata_b), AMOUNT_TO_RECEIVE);
+
+        send_take_partial(&mut s, &designated, variant, AMOUNT_TO_RECEIVE / 2).unwrap();
+        send_take(&mut s, &designated, variant).unwrap();
+        assert_eq!(
+            token_amount(&s.svm, &designated.taker_ata_a),
+            AMOUNT_TO_GIVE
+        );
+        assert_eq!(lamports(&s.svm, &s.escrow_pda), 0);
+    }
+}
@@ -0,0 +1,545 @@
+//! Differential fuzzing of the two instruction variants.
+//!
+//! Every step picks an instruction and random data and accounts, runs the
+//! `unsafe` variant, rolls the touched accounts back, runs the `wincode`
+//! variant, and requires both to agree on success and on every resulting
+//! account. The surviving state carries over to the next step, so offers get
+//! opened, filled, swept in batches, amended, cancelled and tampered with in
+//! random orders, under changing fee settings.
+//!
+//! Set `ESCROW_FUZZ_SEED` to replay a failure and `ESCROW_FUZZ_STEPS` to run
+//! longer.
+
+use escrow_client::{
+    config_address, escrow_address, vault_address, Amend, Cancel, Make, Reclaim, SetConfig, Take,
+    TakeMany, TakeManyOffer, TakePartial, Variant,
+};
+use litesvm::types::TransactionResult;
+use litesvm_token::MintTo;
+use solana_account::Account;
+use solana_clock::Clock;
+use solana_instruction::{AccountMeta, Instruction};
+use solana_message::Message;
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use super::helpers::{
+    load_config, set_fee, setup_make, setup_taker, token_program_of, warp_to, MakeSetup,
+    TakerSetup, DURATION,
+};
+use crate::instructions::wincode::{
+    AmendInstructionData, MakeInstructionData, SetConfigInstructionData, TakeManyInstructionData,
+    TakePartialInstructionData,
+};
+use crate::utils::impl_load_ix;
+
+const SEEDS: u64 = 3;
+const BALANCE: u64 = 1_000_000_000_000;
+
+/// xorshift64*, enough to spread cases without pulling in a fuzzing crate.
+struct Rng(u64);
+
+impl Rng {
+    fn from_env() -> Self {
+        let seed = std::env::var("ESCROW_FUZZ_SEED")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0x5eed_e5c7_0f00_d5u64);
+        Rng(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    fn chance(&mut self, one_in: u64) -> bool {
+        self.below(one_in) == 0
+    }
+
+    /// Mostly small values, with the boundaries that tend to break arithmetic.
+    fn amount(&mut self) -> u64 {
+        match self.below(6) {
+            0 => 0,
+            1 => 1,
+            2 => u64::MAX,
+            3 => self.next_u64(),
+            _ => self.below(1_000_000_000),
+        }
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_u64() as u8).collect()
+    }
+}
+
+fn steps() -> usize {
+    std::env::var("ESCROW_FUZZ_STEPS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(300)
+}
+
+struct World {
+    s: MakeSetup,
+    t: TakerSetup,
+    fee_ata_b: Pubkey,
+}
+
+impl World {
+    fn new() -> Self {
+        let mut s = setup_make(100_000_000, 500_000_000);
+        let t = setup_taker(&mut s, BALANCE);
+        MintTo::new(&mut s.svm, &s.maker, &s.mint_a, &s.maker_ata_a, BALANCE)
+            .send()
+            .unwrap();
+        let fee_ata_b = set_fee(&mut s, Variant::Unsafe, 30);
+        World { s, t, fee_ata_b }
+    }
+
+    fn escrow(&self, seed: u64) -> (Pubkey, Pubkey) {
+        let (escrow_pda, _) = escrow_address(&self.s.maker.pubkey(), seed);
+        let token_program = token_program_of(&self.s.svm, &self.s.mint_a);
+        let escrow_ata = vault_address(&escrow_pda, &self.s.mint_a, &token_program);
+        (escrow_pda, escrow_ata)
+    }
+
+    /// Every non-program account an instruction could touch.
+    fn pool(&self) -> Vec<Pubkey> {
+        let mut pool = vec![
+            self.s.maker.pubkey(),
+            self.s.mint_a,
+            self.s.mint_b,
+            self.s.maker_ata_a,
+            self.t.taker.pubkey(),
+            self.t.taker_ata_a,
+            self.t.taker_ata_b,
+            self.t.maker_ata_b,
+            config_address().0,
+            self.fee_ata_b,
+        ];
+        for seed in 0..SEEDS {
+            let (escrow_pda, escrow_ata) = self.escrow(seed);
+            pool.extend([escrow_pda, escrow_ata]);
+        }
+        pool
+    }
+
+    fn snapshot(&self, pool: &[Pubkey]) -> Vec<Account> {
+        pool.iter()
+            .map(|address| self.s.svm.get_account(address).unwrap_or_default())
+            .collect()
+    }
+
+    fn restore(&mut self, pool: &[Pubkey], accounts: &[Account]) {
+        for (address, account) in pool.iter().zip(accounts) {
+            self.s.svm.set_account(*address, account.clone()).unwrap();
+        }
+    }
+
+    fn send(&mut self, ix: &Instruction, by_taker: bool) -> TransactionResult {
+        let signer = if by_taker {
+            &self.t.taker
+        } else {
+            &self.s.maker
+        };
+
+        self.s.svm.expire_blockhash();
+        let msg = Message::new(&[ix.clone()], Some(&signer.pubkey()));
+        let blockhash = self.s.svm.latest_blockhash();
+        self.s
+            .svm
+            .send_transaction(Transaction::new(&[signer], msg, blockhash))
+    }
+}
+
+/// One generated instruction, built once per variant and identical apart from
+/// the discriminator.
+struct Case {
+    name: &'static str,
+    instructions: [Instruction; 2],
+    by_taker: bool,
+}
+
+impl Case {
+    fn new(name: &'static str, by_taker: bool, build: impl Fn(Variant) -> Instruction) -> Self {
+        Case {
+            name,
+            instructions: Variant::ALL.map(build),
+            by_taker,
+        }
+    }
+
+    /// Applies the same change to both variants' instructions.
+    fn edit(&mut self, mut f: impl FnMut(&mut Instruction)) {
+        for ix in &mut self.instructions {
+            f(ix);
+        }
+    }
+
+    /// Length of the data after the discriminator.
+    fn data_len(&self) -> u64 {
+        self.instructions[0].data.len() as u64 - 1
+    }
+}
+
+fn generate(w: &World, rng: &mut Rng) -> Case {
+    let seed = rng.below(SEEDS);
+    let maker = w.s.maker.pubkey();
+    let token_program = token_program_of(&w.s.svm, &w.s.mint_a);
+
+    let take = Take {
+        taker: w.t.taker.pubkey(),
+        maker,
+        seed,
+        mint_a: w.s.mint_a,
+        mint_b: w.s.mint_b,
+        taker_ata_a: w.t.taker_ata_a,
+        taker_ata_b: w.t.taker_ata_b,
+        maker_ata_b: w.t.maker_ata_b,
+        token_program_a: token_program,
+        token_program_b: token_program_of(&w.s.svm, &w.s.mint_b),
+        fee_ata_b: w.fee_ata_b,
+    };
+    let cancel = Cancel {
+        maker,
+        seed,
+        mint_a: w.s.mint_a,
+        maker_ata_a: w.s.maker_ata_a,
+        token_program,
+    };
+
+    let mut case = match rng.below(8) {
+        0 => {
+            let duration = match rng.below(4) {
+                0 => rng.next_u64() as i64,
+                1 => -(rng.below(1000) as i64),
+                _ => DURATION,
+            };
+            // Public, reserved for the fuzzed taker, or for someone else.
+            let taker = match rng.below(3) {
+                0 => None,
+                1 => Some(w.t.taker.pubkey()),
+                _ => Some(Pubkey::new_unique()),
+            };
+            let make = Make {
+                maker,
+                mint_a: w.s.mint_a,
+                mint_b: w.s.mint_b,
+                maker_ata_a: w.s.maker_ata_a,
+                token_program,
+                seed,
+                amount_to_receive: rng.amount(),
+                amount_to_give: rng.amount(),
+                duration,
+                taker,
+            };
+            let mut case = Case::new("make", false, |v| make.instruction(v));
+            // A bump other than the canonical one.
+            if rng.chance(8) {
+                let bump = rng.next_u64() as u8;
+                case.edit(|ix| *ix.data.last_mut().unwrap() = bump);
+            }
+            // Truncated or padded data.
+            if rng.chance(6) {
+                let len = 1 + rng.below(case.data_len()) as usize;
+                case.edit(|ix| ix.data.truncate(len));
+            } else if rng.chance(6) {
+                let extra = rng.bytes(rng.below(8) as usize);
+                case.edit(|ix| ix.data.extend(&extra));
+            }
+            case
+        }
+        1 => {
+            let mut case = Case::new("take", true, |v| take.instruction(v));
+            let extra = rng.bytes(rng.below(3) as usize);
+            case.edit(|ix| ix.data.extend(&extra));
+            case
+        }
+        2 => {
+            let partial = TakePartial {
+                take,
+                fill: rng.amount(),
+            };
+            let mut case = Case::new("take_partial", true, |v| partial.instruction(v));
+            if rng.chance(6) {
+                let len = 1 + rng.below(8) as usize;
+                case.edit(|ix| ix.data.truncate(len));
+            }
+            case
+        }
+        3 => Case::new("cancel", false, |v| cancel.instruction(v)),
+        4 => {
+            let amend = Amend {
+                maker,
+                seed,
+                mint_a: w.s.mint_a,
+                maker_ata_a: w.s.maker_ata_a,
+                token_program,
+                amount_to_receive: rng.amount(),
+                amount_to_give: rng.amount(),
+            };
+            let mut case = Case::new("amend", false, |v| amend.instruction(v));
+            if rng.chance(6) {
+                let len = 1 + rng.below(16) as usize;
+                case.edit(|ix| ix.data.truncate(len));
+            }
+            case
+        }
+        5 => {
+            let set_config = SetConfig {
+                authority: maker,
+                admin: maker,
+                fee_recipient: load_config(&w.s.svm).fee_recipient,
+                fee_basis_points: rng.below(1_200) as u16,
+            };
+            Case::new("set_config", false, |v| set_config.instruction(v))
+        }
+        6 => {
+            // Up to one offer per seed, possibly the same one twice.
+            let offers = (0..1 + rng.below(SEEDS))
+                .map(|_| TakeManyOffer {
+                    maker,
+                    seed: rng.below(SEEDS),
+                    maker_ata_b: w.t.maker_ata_b,
+                })
+                .collect();
+            let take_many = TakeMany {
+                taker: take.taker,
+                mint_a: take.mint_a,
+                mint_b: take.mint_b,
+                taker_ata_a: take.taker_ata_a,
+                taker_ata_b: take.taker_ata_b,
+                token_program_a: take.token_program_a,
+                token_program_b: take.token_program_b,
+                fee_ata_b: take.fee_ata_b,
+                offers,
+            };
+            let mut case = Case::new("take_many", true, |v| take_many.instruction(v));
+            // A count that does not match the accounts.
+            if rng.chance(6) {
+                let count = rng.below(SEEDS + 2) as u8;
+                case.edit(|ix| ix.data[1] = count);
+            }
+            case
+        }
+        _ => {
+            let reclaim = Reclaim {
+                maker,
+                seed,
+                mint_a: w.s.mint_a,
+                maker_ata_a: w.s.maker_ata_a,
+                token_program,
+            };
+            Case::new("reclaim", true, |v| reclaim.instruction(v))
+        }
+    };
+
+    // Swap a non-signer account for another one the program might confuse it with.
+    if rng.chance(4) {
+        let pool = w.pool();
+        let accounts = case.instructions[0].accounts.len() as u64;
+        let index = 1 + rng.below(accounts - 1) as usize;
+        let address = pool[rng.below(pool.len() as u64) as usize];
+        case.edit(|ix| ix.accounts[index] = AccountMeta::new(address, false));
+    }
+    // Dropping trailing accounts must fail the same way in both variants.
+    if rng.chance(10) {
+        let len = rng.below(case.instructions[0].accounts.len() as u64) as usize;
+        case.edit(|ix| ix.accounts.truncate(len.max(1)));
+    }
+    case
+}
+
+/// Changes the shared state before a step: corrupts an escrow or moves time.
+fn perturb(w: &mut World, rng: &mut Rng) {
+    if rng.chance(12) {
+        let (escrow_pda, _) = w.escrow(rng.below(SEEDS));
+        if let Some(mut account) = w.s.svm.get_account(&escrow_pda) {
+            if !account.data.is_empty() {
+                let index = rng.below(account.data.len() as u64) as usize;
+                account.data[index] = rng.next_u64() as u8;
+                w.s.svm.set_account(escrow_pda, account).unwrap();
+            }
+        }
+    }
+    if rng.chance(10) {
+        let now = w.s.svm.get_sysvar::<Clock>().unix_timestamp;
+        warp_to(&mut w.s.svm, now + rng.below(2 * DURATION as u64) as i64);
+    }
+}
+
+#[test]
+fn test_variants_agree() {
+    let mut rng = Rng::from_env();
+    let seed = rng.0;
+    let mut w = World::new();
+    let pool = w.pool();
+
+    for step in 0..steps() {
+        perturb(&mut w, &mut rng);
+        let case = generate(&w, &mut rng);
+        let before = w.snapshot(&pool);
+
+        let [unsafe_ix, wincode_ix] = &case.instructions;
+        let unsafe_result = w.send(unsafe_ix, case.by_taker).is_ok();
+        let unsafe_after = w.snapshot(&pool);
+
+        w.restore(&pool, &before);
+        let wincode_result = w.send(wincode_ix, case.by_taker).is_ok();
+        let wincode_after = w.snapshot(&pool);
+
+        let context = format!(
+            "ESCROW_FUZZ_SEED={seed} step {step}: {}, data {:?}",
+            case.name,
+            &unsafe_ix.data[1..]
+        );
+        assert_eq!(unsafe_result, wincode_result, "{context}");
+        for ((address, a), b) in pool.iter().zip(&unsafe_after).zip(&wincode_after) {
+            assert_eq!(a, b, "{context}: account {address} differs");
+        }
+    }
+}
+
+/// Make data as the `unsafe` variant lays it out, loaded with `impl_load_ix`.
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+struct RawMakeData {
+    seed: u64,
+    amount_to_receive: u64,
+    amount_to_give: u64,
+    duration: i64,
+    taker: [u8; 32],
+    bump: u8,
+}
+
+impl_load_ix!(RawMakeData);
+
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+struct RawTakePartialData {
+    fill: u64,
+}
+
+impl_load_ix!(RawTakePartialData);
+
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+struct RawAmendData {
+    amount_to_receive: u64,
+    amount_to_give: u64,
+}
+
+impl_load_ix!(RawAmendData);
+
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+struct RawSetConfigData {
+    admin: [u8; 32],
+    fee_recipient: [u8; 32],
+    fee_basis_points: u16,
+}
+
+impl_load_ix!(RawSetConfigData);
+
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+struct RawTakeManyData {
+    count: u8,
+}
+
+impl_load_ix!(RawTakeManyData);
+
+#[test]
+fn test_instruction_data_decoding() {
+    let mut rng = Rng::from_env();
+
+    for _ in 0..20_000 {
+        let len = rng.below(80) as usize;
+        let data = rng.bytes(len);
+
+        let raw = RawMakeData::load(&data);
+        let decoded = ::wincode::deserialize::<MakeInstructionData>(&data);
+        assert_eq!(raw.is_ok(), len >= 65, "{data:?}");
+        if len < 65 {
+            assert!(decoded.is_err(), "{data:?}");
+        }
+        if let (Ok(raw), Ok(decoded)) = (raw, decoded) {
+            let RawMakeData {
+                seed,
+                amount_to_receive,
+                amount_to_give,
+                duration,
+                taker,
+                bump,
+            } = raw;
+            assert_eq!(seed, decoded.seed);
+            assert_eq!(amount_to_receive, decoded.amount_to_receive);
+            assert_eq!(amount_to_give, decoded.amount_to_give);
+            assert_eq!(duration, decoded.duration);
+            assert_eq!(taker, decoded.taker);
+            assert_eq!(bump, decoded.bump);
+        }
+
+        let raw = RawTakePartialData::load(&data);
+        let decoded = ::wincode::deserialize::<TakePartialInstructionData>(&data);
+        assert_eq!(raw.is_ok(), len >= 8, "{data:?}");
+        if len < 8 {
+            assert!(decoded.is_err(), "{data:?}");
+        }
+        if let (Ok(raw), Ok(decoded)) = (raw, decoded) {
+            let RawTakePartialData { fill } = raw;
+            assert_eq!(fill, decoded.fill);
+        }
+
+        let raw = RawAmendData::load(&data);
+        let decoded = ::wincode::deserialize::<AmendInstructionData>(&data);
+        assert_eq!(raw.is_ok(), len >= 16, "{data:?}");
+        if len < 16 {
+            assert!(decoded.is_err(), "{data:?}");
+        }
+        if let (Ok(raw), Ok(decoded)) = (raw, decoded) {
+            let RawAmendData {
+                amount_to_receive,
+                amount_to_give,
+            } = raw;
+            assert_eq!(amount_to_receive, decoded.amount_to_receive);
+            assert_eq!(amount_to_give, decoded.amount_to_give);
+        }
+
+        let raw = RawSetConfigData::load(&data);
+        let decoded = ::wincode::deserialize::<SetConfigInstructionData>(&data);
+        assert_eq!(raw.is_ok(), len >= 66, "{data:?}");
+        if len < 66 {
+            assert!(decoded.is_err(), "{data:?}");
+        }
+        if let (Ok(raw), Ok(decoded)) = (raw, decoded) {
+            let RawSetConfigData {
+                admin,
+                fee_recipient,
+                fee_basis_points,
+            } = raw;
+            assert_eq!(admin, decoded.admin);
+            assert_eq!(fee_recipient, decoded.fee_recipient);
+            assert_eq!(fee_basis_points, decoded.fee_basis_points);
+        }
+
+        let raw = RawTakeManyData::load(&data);
+        let decoded = ::wincode::deserialize::<TakeManyInstructionData>(&data);
+        assert_eq!(raw.is_ok(), len >= 1, "{data:?}");
+        if len < 1 {
+            assert!(decoded.is_err(), "{data:?}");
+        }
+        if let (Ok(raw), Ok(decoded)) = (raw, decoded) {
+            let RawTakeManyData { count } = raw;
+            assert_eq!(count, decoded.count);
+        }
+    }
+}
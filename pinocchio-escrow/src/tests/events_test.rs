@@ -0,0 +1,153 @@
+use escrow_client::{escrow_address, Variant};
+use escrow_events::{parse_logs, Amended, Cancelled, Event, EventError, Made, Taken};
+use litesvm_token::MintTo;
+use solana_signer::Signer;
+
+use super::helpers::{
+    send_amend, send_cancel, send_make, send_take, send_take_many, send_take_partial, set_fee,
+    setup_make_many, setup_make_with, setup_taker, Offer, DURATION,
+};
+
+const AMOUNT_TO_RECEIVE: u64 = 100_000_000;
+const AMOUNT_TO_GIVE: u64 = 500_000_000;
+
+#[test]
+fn test_events() {
+    for variant in Variant::ALL {
+        let mut s = setup_make_with(variant, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE);
+        let maker = s.maker.pubkey();
+        set_fee(&mut s, variant, 100);
+
+        MintTo::new(
+            &mut s.svm,
+            &s.maker,
+            &s.mint_a,
+            &s.maker_ata_a,
+            AMOUNT_TO_GIVE,
+        )
+        .send()
+        .unwrap();
+        let offer = Offer {
+            seed: 1,
+            amount_to_receive: AMOUNT_TO_RECEIVE,
+            amount_to_give: AMOUNT_TO_GIVE,
+            duration: DURATION,
+            taker: None,
+        };
+        let tx = send_make(
+            &mut s.svm,
+            &s.maker,
+            &s.mint_a,
+            &s.mint_b,
+            &s.maker_ata_a,
+            variant,
+            offer,
+        )
+        .unwrap();
+        let (other_escrow, _) = escrow_address(&maker, 1);
+        assert_eq!(
+            parse_logs(&tx.logs).unwrap(),
+            [Event::Made(Made {
+                escrow: other_escrow,
+                maker,
+                mint_a: s.mint_a,
+                mint_b: s.mint_b,
+                amount_to_receive: AMOUNT_TO_RECEIVE,
+                amount_to_give: AMOUNT_TO_GIVE,
+            })]
+        );
+
+        // Each fill logs its own share, fee included.
+        let t = setup_taker(&mut s, AMOUNT_TO_RECEIVE);
+        let taken = Taken {
+            escrow: s.escrow_pda,
+            maker,
+            taker: t.taker.pubkey(),
+            mint_a: s.mint_a,
+            mint_b: s.mint_b,
+            amount_paid: AMOUNT_TO_RECEIVE / 2,
+            amount_received: AMOUNT_TO_GIVE / 2,
+            fee: AMOUNT_TO_RECEIVE / 2 / 100,
+        };
+        let tx = send_take_partial(&mut s, &t, variant, AMOUNT_TO_RECEIVE / 2).unwrap();
+        assert_eq!(parse_logs(&tx.logs).unwrap(), [Event::Taken(taken)]);
+        let tx = send_take(&mut s, &t, variant).unwrap();
+        assert_eq!(parse_logs(&tx.logs).unwrap(), [Event::Taken(taken)]);
+
+        // Amending logs the new terms.
+        s.seed = 1;
+        let tx = send_amend(&mut s, variant, 2 * AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE / 2).unwrap();
+        assert_eq!(
+            parse_logs(&tx.logs).unwrap(),
+            [Event::Amended(Amended {
+                escrow: other_escrow,
+                maker,
+                mint_a: s.mint_a,
+                amount_to_receive: 2 * AMOUNT_TO_RECEIVE,
+                amount_to_give: AMOUNT_TO_GIVE / 2,
+            })]
+        );
+
+        let tx = send_cancel(&mut s, variant).unwrap();
+        assert_eq!(
+            parse_logs(&tx.logs).unwrap(),
+            [Event::Cancelled(Cancelled {
+                escrow: other_escrow,
+                maker,
+                mint_a: s.mint_a,
+                amount_returned: AMOUNT_TO_GIVE / 2,
+            })]
+        );
+    }
+}
+
+#[test]
+fn test_events_take_many() {
+    for variant in Variant::ALL {
+        let mut s = setup_make_many(variant, 2, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE);
+        let t = setup_taker(&mut s, 2 * AMOUNT_TO_RECEIVE);
+
+        let tx = send_take_many(&mut s, &t, variant, &[0, 1]).unwrap();
+        let escrows: Vec<_> = parse_logs(&tx.logs)
+            .unwrap()
+            .into_iter()
+            .map(|event| match event {
+                Event::Taken(taken) => taken.escrow,
+                other => panic!("unexpected event {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            escrows,
+            [
+                escrow_address(&s.maker.pubkey(), 0).0,
+                escrow_address(&s.maker.pubkey(), 1).0
+            ]
+        );
+    }
+}
+
+#[test]
+fn test_event_decoding() {
+    assert_eq!(Event::decode(&[]), Err(EventError::InvalidLength(0)));
+    assert_eq!(
+        Event::decode(&[9]),
+        Err(EventError::UnknownDiscriminator(9))
+    );
+    assert_eq!(Event::decode(&[0; 10]), Err(EventError::InvalidLength(10)));
+    assert_eq!(Event::decode(&[3; 10]), Err(EventError::InvalidLength(10)));
+
+    // Data logged by another program is not the escrow's.
+    let other = solana_sdk_ids::system_program::ID.to_string();
+    let logs = [
+        format!("Program {other} invoke [1]"),
+        "Program data: AA==".to_string(),
+        format!("Program {other} success"),
+    ];
+    assert_eq!(parse_logs(&logs), Ok(vec![]));
+
+    let logs = [
+        format!("Program {} invoke [1]", escrow_client::ID),
+        "Program data: !!".to_string(),
+    ];
+    assert_eq!(parse_logs(&logs), Err(EventError::InvalidBase64));
+}
@@ -0,0 +1,170 @@
+use escrow_client::{config_address, program_data_address, SetConfig, Variant};
+use solana_account::Account;
+use solana_instruction::AccountMeta;
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+
+use super::helpers::{
+    create_config, load_config, send_as_maker, send_as_taker, send_take, send_take_partial,
+    set_fee, setup_make_with, setup_taker, take, token_amount,
+};
+
+const AMOUNT_TO_RECEIVE: u64 = 100_000_000;
+const AMOUNT_TO_GIVE: u64 = 500_000_000;
+/// 2.5%.
+const FEE_BASIS_POINTS: u16 = 250;
+const FEE: u64 = AMOUNT_TO_RECEIVE * FEE_BASIS_POINTS as u64 / 10_000;
+
+#[test]
+fn test_take_without_config() {
+    for variant in Variant::ALL {
+        let mut s = setup_make_with(variant, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE);
+        let t = setup_taker(&mut s, AMOUNT_TO_RECEIVE);
+        assert!(s.svm.get_account(&config_address().0).is_none());
+
+        // Until the config is created, no fee is charged.
+        send_take(&mut s, &t, variant).unwrap();
+
+        assert_eq!(token_amount(&s.svm, &t.maker_ata_b), AMOUNT_TO_RECEIVE);
+        assert_eq!(token_amount(&s.svm, &t.taker_ata_a), AMOUNT_TO_GIVE);
+    }
+}
+
+#[test]
+fn test_take_routes_fee() {
+    for variant in Variant::ALL {
+        let mut s = setup_make_with(variant, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE);
+        let fee_ata_b = set_fee(&mut s, variant, FEE_BASIS_POINTS);
+        let t = setup_taker(&mut s, AMOUNT_TO_RECEIVE);
+
+        send_take(&mut s, &t, variant).unwrap();
+
+        // The taker pays the asked price; the fee comes out of the maker's side.
+        assert_eq!(token_amount(&s.svm, &t.taker_ata_b), 0);
+        assert_eq!(token_amount(&s.svm, &fee_ata_b), FEE);
+        assert_eq!(
+            token_amount(&s.svm, &t.maker_ata_b),
+            AMOUNT_TO_RECEIVE - FEE
+        );
+        assert_eq!(token_amount(&s.svm, &t.taker_ata_a), AMOUNT_TO_GIVE);
+    }
+}
+
+#[test]
+fn test_take_partial_routes_fee() {
+    for variant in Variant::ALL {
+        let mut s = setup_make_with(variant, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE);
+        let fee_ata_b = set_fee(&mut s, variant, FEE_BASIS_POINTS);
+        let t = setup_taker(&mut s, AMOUNT_TO_RECEIVE);
+
+        send_take_partial(&mut s, &t, variant, AMOUNT_TO_RECEIVE / 4).unwrap();
+        send_take_partial(&mut s, &t, variant, AMOUNT_TO_RECEIVE * 3 / 4).unwrap();
+
+        assert_eq!(token_amount(&s.svm, &fee_ata_b), FEE);
+        assert_eq!(
+            token_amount(&s.svm, &t.maker_ata_b),
+            AMOUNT_TO_RECEIVE - FEE
+        );
+    }
+}
+
+#[test]
+fn test_fee_paid_to_configured_recipient() {
+    for variant in Variant::ALL {
+        let mut s = setup_make_with(variant, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE);
+        set_fee(&mut s, variant, FEE_BASIS_POINTS);
+        let t = setup_taker(&mut s, AMOUNT_TO_RECEIVE);
+
+        // The taker routes the fee back to their own account.
+        let mut ix = take(&s, &t).instruction(variant);
+        ix.accounts[12] = AccountMeta::new(t.taker_ata_b, false);
+        assert!(send_as_taker(&mut s, &t, ix).is_err());
+
+        // Or passes a config they made up.
+        let mut ix = take(&s, &t).instruction(variant);
+        ix.accounts[11] = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+        assert!(send_as_taker(&mut s, &t, ix).is_err());
+
+        send_take(&mut s, &t, variant).unwrap();
+    }
+}
+
+#[test]
+fn test_create_config() {
+    for variant in Variant::ALL {
+        let mut s = setup_make_with(variant, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE);
+        let t = setup_taker(&mut s, 0);
+        let claim = SetConfig {
+            authority: t.taker.pubkey(),
+            admin: t.taker.pubkey(),
+            fee_recipient: t.taker.pubkey(),
+            fee_basis_points: 0,
+        };
+
+        // Nobody but the upgrade authority can create the config and claim it.
+        let ix = claim.instruction(variant);
+        assert!(send_as_taker(&mut s, &t, ix).is_err());
+
+        // Nor with a `ProgramData` lookalike that names someone else.
+        let mut data = s.svm.get_account(&program_data_address()).unwrap().data;
+        data[13..45].copy_from_slice(t.taker.pubkey().as_ref());
+        let fake = Pubkey::new_unique();
+        let account = Account {
+            lamports: s.svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: solana_sdk_ids::bpf_loader_upgradeable::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        s.svm.set_account(fake, account).unwrap();
+        let mut ix = claim.instruction(variant);
+        ix.accounts[3] = AccountMeta::new_readonly(fake, false);
+        assert!(send_as_taker(&mut s, &t, ix).is_err());
+
+        // Lamports sent to the address ahead of time do not block creation.
+        let config = config_address().0;
+        s.svm.airdrop(&config, 1_000_000).unwrap();
+        create_config(&mut s, variant).unwrap();
+
+        let account = s.svm.get_account(&config).unwrap();
+        assert_eq!(account.owner, escrow_client::ID);
+        assert_eq!(load_config(&s.svm).admin, s.maker.pubkey());
+    }
+}
+
+#[test]
+fn test_set_config() {
+    for variant in Variant::ALL {
+        let mut s = setup_make_with(variant, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE);
+        set_fee(&mut s, variant, 0);
+        let t = setup_taker(&mut s, 0);
+        let update = |authority: Pubkey, admin: Pubkey, fee_basis_points| SetConfig {
+            authority,
+            admin,
+            fee_recipient: admin,
+            fee_basis_points,
+        };
+
+        // Only the admin may update the config.
+        let ix = update(t.taker.pubkey(), t.taker.pubkey(), 0).instruction(variant);
+        assert!(send_as_taker(&mut s, &t, ix).is_err());
+
+        // Fees above the 10% cap are rejected.
+        let ix = update(s.maker.pubkey(), s.maker.pubkey(), 1_001).instruction(variant);
+        assert!(send_as_maker(&mut s, ix).is_err());
+
+        // The admin can hand the config over.
+        let ix = update(s.maker.pubkey(), t.taker.pubkey(), 100).instruction(variant);
+        send_as_maker(&mut s, ix).unwrap();
+        let config = load_config(&s.svm);
+        assert_eq!(config.admin, t.taker.pubkey());
+        assert_eq!(config.fee_recipient, t.taker.pubkey());
+        assert_eq!(config.fee_basis_points, 100);
+
+        let ix = update(s.maker.pubkey(), s.maker.pubkey(), 0).instruction(variant);
+        assert!(send_as_maker(&mut s, ix).is_err());
+        let ix = update(t.taker.pubkey(), s.maker.pubkey(), 0).instruction(variant);
+        send_as_taker(&mut s, &t, ix).unwrap();
+        assert_eq!(load_config(&s.svm).admin, s.maker.pubkey());
+    }
+}
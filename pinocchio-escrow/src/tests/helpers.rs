@@ -1,8 +1,15 @@
 use std::path::PathBuf;
 
-use litesvm::LiteSVM;
-use litesvm_token::{spl_token, CreateAssociatedTokenAccount, CreateMint, MintTo};
-use solana_instruction::{AccountMeta, Instruction};
+use escrow_client::{
+    config_address, escrow_address, program_data_address, token_account_address, vault_address,
+    Amend, Cancel, Config, Escrow, Make, Reclaim, SetConfig, Take, TakeMany, TakeManyOffer,
+    TakePartial, Variant, NATIVE_MINT,
+};
+use litesvm::{types::TransactionResult, LiteSVM};
+use litesvm_token::{CreateAssociatedTokenAccount, CreateMint, MintTo};
+use solana_account::Account;
+use solana_clock::Clock;
+use solana_instruction::Instruction;
 use solana_keypair::Keypair;
 use solana_message::Message;
 use solana_native_token::LAMPORTS_PER_SOL;
@@ -10,13 +17,10 @@ use solana_pubkey::Pubkey;
 use solana_signer::Signer;
 use solana_transaction::Transaction;
 
-pub const TOKEN_PROGRAM_ID: Pubkey = spl_token::ID;
-pub const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
-
-pub fn program_id() -> Pubkey {
-    Pubkey::from(crate::ID)
-}
+/// Lifetime of the offers opened by `setup_make`/`setup_make_v2`: one day.
+pub const DURATION: i64 = 86_400;
 
+/// Deploys the program with `payer` as its upgrade authority.
 pub fn load_svm() -> (LiteSVM, Keypair) {
     let mut svm = LiteSVM::new();
     let payer = Keypair::new();
@@ -27,23 +31,103 @@ pub fn load_svm() -> (LiteSVM, Keypair) {
         .join("target/sbpf-solana-solana/release/escrow.so");
 
     let program_data = std::fs::read(so_path).expect("Failed to read program SO file");
-    svm.add_program(program_id(), &program_data).unwrap();
+    deploy_upgradeable(&mut svm, &program_data, &payer.pubkey());
 
     (svm, payer)
 }
 
+/// Writes the program and its `ProgramData` account the way the upgradeable
+/// loader lays them out after `solana program deploy`, since `add_program`
+/// uses the non-upgradeable loader and `set_config` checks the upgrade
+/// authority.
+fn deploy_upgradeable(svm: &mut LiteSVM, elf: &[u8], upgrade_authority: &Pubkey) {
+    let loader = solana_sdk_ids::bpf_loader_upgradeable::ID;
+    let program_data = program_data_address();
+
+    // [state:4 = 3 (ProgramData)][slot:8][has_authority:1][authority:32][elf]
+    let mut data = vec![3, 0, 0, 0];
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(upgrade_authority.as_ref());
+    data.extend_from_slice(elf);
+    let account = Account {
+        lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner: loader,
+        executable: false,
+        rent_epoch: 0,
+    };
+    svm.set_account(program_data, account).unwrap();
+
+    // [state:4 = 2 (Program)][programdata_address:32]
+    let mut data = vec![2, 0, 0, 0];
+    data.extend_from_slice(program_data.as_ref());
+    let account = Account {
+        lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner: loader,
+        executable: true,
+        rent_epoch: 0,
+    };
+    svm.set_account(escrow_client::ID, account).unwrap();
+}
+
 pub struct MakeSetup {
     pub svm: LiteSVM,
     pub maker: Keypair,
     pub mint_a: Pubkey,
     pub mint_b: Pubkey,
+    pub seed: u64,
     pub escrow_pda: Pubkey,
     pub escrow_ata: Pubkey,
     pub maker_ata_a: Pubkey,
     pub make_cu: u64,
 }
 
-pub fn setup_make_v2(amount_to_receive: u64, amount_to_give: u64) -> MakeSetup {
+/// Arguments of a `make` instruction.
+#[derive(Clone, Copy)]
+pub struct Offer {
+    pub seed: u64,
+    pub amount_to_receive: u64,
+    pub amount_to_give: u64,
+    /// Seconds from `make` until the offer expires.
+    pub duration: i64,
+    /// The only key allowed to take the offer, if any.
+    pub taker: Option<Pubkey>,
+}
+
+/// Sends a `make` for `offer` through `variant`.
+pub fn send_make(
+    svm: &mut LiteSVM,
+    maker: &Keypair,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+    maker_ata_a: &Pubkey,
+    variant: Variant,
+    offer: Offer,
+) -> TransactionResult {
+    let ix = Make {
+        maker: maker.pubkey(),
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        maker_ata_a: *maker_ata_a,
+        token_program: token_program_of(svm, mint_a),
+        seed: offer.seed,
+        amount_to_receive: offer.amount_to_receive,
+        amount_to_give: offer.amount_to_give,
+        duration: offer.duration,
+        taker: offer.taker,
+    }
+    .instruction(variant);
+
+    let msg = Message::new(&[ix], Some(&maker.pubkey()));
+    let blockhash = svm.latest_blockhash();
+    svm.send_transaction(Transaction::new(&[maker], msg, blockhash))
+}
+
+/// Opens an offer with seed 0 through `variant`, funding the maker with exactly
+/// `amount_to_give`.
+pub fn setup_make_with(variant: Variant, amount_to_receive: u64, amount_to_give: u64) -> MakeSetup {
     let (mut svm, maker) = load_svm();
 
     let mint_a = CreateMint::new(&mut svm, &maker)
@@ -63,54 +147,37 @@ pub fn setup_make_v2(amount_to_receive: u64, amount_to_give: u64) -> MakeSetup {
         .send()
         .unwrap();
 
-    let (escrow_pda, bump) =
-        Pubkey::find_program_address(&[b"escrow", maker.pubkey().as_ref()], &program_id());
-
-    let escrow_ata =
-        spl_associated_token_account::get_associated_token_address(&escrow_pda, &mint_a);
-
     MintTo::new(&mut svm, &maker, &mint_a, &maker_ata_a, amount_to_give)
         .send()
         .unwrap();
 
-    let data = [
-        vec![3u8],
-        amount_to_receive.to_le_bytes().to_vec(),
-        amount_to_give.to_le_bytes().to_vec(),
-        vec![bump],
-    ]
-    .concat();
-
-    let ix = Instruction {
-        program_id: program_id(),
-        accounts: vec![
-            AccountMeta::new(maker.pubkey(), true),
-            AccountMeta::new(mint_a, false),
-            AccountMeta::new(mint_b, false),
-            AccountMeta::new(escrow_pda, false),
-            AccountMeta::new(maker_ata_a, false),
-            AccountMeta::new(escrow_ata, false),
-            AccountMeta::new(solana_sdk_ids::system_program::ID, false),
-            AccountMeta::new(TOKEN_PROGRAM_ID, false),
-            AccountMeta::new(
-                ASSOCIATED_TOKEN_PROGRAM_ID.parse::<Pubkey>().unwrap(),
-                false,
-            ),
-        ],
-        data,
+    let offer = Offer {
+        seed: 0,
+        amount_to_receive,
+        amount_to_give,
+        duration: DURATION,
+        taker: None,
     };
+    let tx = send_make(
+        &mut svm,
+        &maker,
+        &mint_a,
+        &mint_b,
+        &maker_ata_a,
+        variant,
+        offer,
+    )
+    .unwrap();
 
-    let msg = Message::new(&[ix], Some(&maker.pubkey()));
-    let blockhash = svm.latest_blockhash();
-    let tx = svm
-        .send_transaction(Transaction::new(&[&maker], msg, blockhash))
-        .unwrap();
+    let (escrow_pda, _) = escrow_address(&maker.pubkey(), offer.seed);
+    let escrow_ata = vault_address(&escrow_pda, &mint_a, &token_program_of(&svm, &mint_a));
 
     MakeSetup {
         svm,
         maker,
         mint_a,
         mint_b,
+        seed: offer.seed,
         escrow_pda,
         escrow_ata,
         maker_ata_a,
@@ -118,77 +185,337 @@ pub fn setup_make_v2(amount_to_receive: u64, amount_to_give: u64) -> MakeSetup {
     }
 }
 
+/// Like `setup_make_with`, but the maker opens `count` identical offers with
+/// seeds `0..count`.
+pub fn setup_make_many(
+    variant: Variant,
+    count: u64,
+    amount_to_receive: u64,
+    amount_to_give: u64,
+) -> MakeSetup {
+    let mut s = setup_make_with(variant, amount_to_receive, amount_to_give);
+    MintTo::new(
+        &mut s.svm,
+        &s.maker,
+        &s.mint_a,
+        &s.maker_ata_a,
+        (count - 1) * amount_to_give,
+    )
+    .send()
+    .unwrap();
+
+    for seed in 1..count {
+        let offer = Offer {
+            seed,
+            amount_to_receive,
+            amount_to_give,
+            duration: DURATION,
+            taker: None,
+        };
+        send_make(
+            &mut s.svm,
+            &s.maker,
+            &s.mint_a,
+            &s.mint_b,
+            &s.maker_ata_a,
+            variant,
+            offer,
+        )
+        .unwrap();
+    }
+    s
+}
+
+pub fn setup_make_v2(amount_to_receive: u64, amount_to_give: u64) -> MakeSetup {
+    setup_make_with(Variant::Wincode, amount_to_receive, amount_to_give)
+}
+
 pub fn setup_make(amount_to_receive: u64, amount_to_give: u64) -> MakeSetup {
-    let (mut svm, maker) = load_svm();
+    setup_make_with(Variant::Unsafe, amount_to_receive, amount_to_give)
+}
 
-    let mint_a = CreateMint::new(&mut svm, &maker)
-        .decimals(6)
-        .authority(&maker.pubkey())
-        .send()
+/// The token program that owns `mint`, SPL Token or Token-2022, or the system
+/// program for native SOL.
+pub fn token_program_of(svm: &LiteSVM, mint: &Pubkey) -> Pubkey {
+    if *mint == NATIVE_MINT {
+        return NATIVE_MINT;
+    }
+    svm.get_account(mint).unwrap().owner
+}
+
+/// Reads the `amount` field of a token account of either program.
+pub fn token_amount(svm: &LiteSVM, address: &Pubkey) -> u64 {
+    let account = svm.get_account(address).unwrap();
+    u64::from_le_bytes(account.data[64..72].try_into().unwrap())
+}
+
+pub fn lamports(svm: &LiteSVM, address: &Pubkey) -> u64 {
+    svm.get_account(address)
+        .map_or(0, |account| account.lamports)
+}
+
+pub struct TakerSetup {
+    pub taker: Keypair,
+    pub taker_ata_a: Pubkey,
+    pub taker_ata_b: Pubkey,
+    pub maker_ata_b: Pubkey,
+}
+
+/// Funds a new taker and creates the token accounts a take needs, minting
+/// `amount_b` of `mint_b` to the taker.
+pub fn setup_taker(s: &mut MakeSetup, amount_b: u64) -> TakerSetup {
+    let taker = Keypair::new();
+    s.svm
+        .airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL)
         .unwrap();
 
-    let mint_b = CreateMint::new(&mut svm, &maker)
-        .decimals(6)
-        .authority(&maker.pubkey())
+    let taker_ata_a = CreateAssociatedTokenAccount::new(&mut s.svm, &taker, &s.mint_a)
+        .owner(&taker.pubkey())
+        .send()
+        .unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut s.svm, &taker, &s.mint_b)
+        .owner(&taker.pubkey())
+        .send()
+        .unwrap();
+    let maker_ata_b = CreateAssociatedTokenAccount::new(&mut s.svm, &taker, &s.mint_b)
+        .owner(&s.maker.pubkey())
         .send()
         .unwrap();
 
-    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut svm, &maker, &mint_a)
-        .owner(&maker.pubkey())
+    MintTo::new(&mut s.svm, &s.maker, &s.mint_b, &taker_ata_b, amount_b)
         .send()
         .unwrap();
 
-    let (escrow_pda, bump) =
-        Pubkey::find_program_address(&[b"escrow", maker.pubkey().as_ref()], &program_id());
+    TakerSetup {
+        taker,
+        taker_ata_a,
+        taker_ata_b,
+        maker_ata_b,
+    }
+}
 
-    let escrow_ata =
-        spl_associated_token_account::get_associated_token_address(&escrow_pda, &mint_a);
+/// `take` of the whole escrow in `s` by `t`.
+pub fn take(s: &MakeSetup, t: &TakerSetup) -> Take {
+    Take {
+        taker: t.taker.pubkey(),
+        maker: s.maker.pubkey(),
+        seed: s.seed,
+        mint_a: s.mint_a,
+        mint_b: s.mint_b,
+        taker_ata_a: t.taker_ata_a,
+        taker_ata_b: t.taker_ata_b,
+        maker_ata_b: t.maker_ata_b,
+        token_program_a: token_program_of(&s.svm, &s.mint_a),
+        token_program_b: token_program_of(&s.svm, &s.mint_b),
+        fee_ata_b: fee_ata_b(&s.svm, &s.mint_b),
+    }
+}
 
-    MintTo::new(&mut svm, &maker, &mint_a, &maker_ata_a, amount_to_give)
-        .send()
-        .unwrap();
+/// `take_many` of the maker's offers with `seeds`.
+pub fn take_many(s: &MakeSetup, t: &TakerSetup, seeds: &[u64]) -> TakeMany {
+    let offers = seeds
+        .iter()
+        .map(|&seed| TakeManyOffer {
+            maker: s.maker.pubkey(),
+            seed,
+            maker_ata_b: t.maker_ata_b,
+        })
+        .collect();
+    let take = take(s, t);
+    TakeMany {
+        taker: take.taker,
+        mint_a: take.mint_a,
+        mint_b: take.mint_b,
+        taker_ata_a: take.taker_ata_a,
+        taker_ata_b: take.taker_ata_b,
+        token_program_a: take.token_program_a,
+        token_program_b: take.token_program_b,
+        fee_ata_b: take.fee_ata_b,
+        offers,
+    }
+}
 
-    let data = [
-        vec![0u8],
-        amount_to_receive.to_le_bytes().to_vec(),
-        amount_to_give.to_le_bytes().to_vec(),
-        vec![bump],
-    ]
-    .concat();
-
-    let ix = Instruction {
-        program_id: program_id(),
-        accounts: vec![
-            AccountMeta::new(maker.pubkey(), true),
-            AccountMeta::new(mint_a, false),
-            AccountMeta::new(mint_b, false),
-            AccountMeta::new(escrow_pda, false),
-            AccountMeta::new(maker_ata_a, false),
-            AccountMeta::new(escrow_ata, false),
-            AccountMeta::new(solana_sdk_ids::system_program::ID, false),
-            AccountMeta::new(TOKEN_PROGRAM_ID, false),
-            AccountMeta::new(
-                ASSOCIATED_TOKEN_PROGRAM_ID.parse::<Pubkey>().unwrap(),
-                false,
-            ),
-        ],
-        data,
+pub fn load_config(svm: &LiteSVM) -> Config {
+    Config::decode(&svm.get_account(&config_address().0).unwrap().data).unwrap()
+}
+
+/// The configured fee recipient's account for `mint`. Before any config
+/// exists no fee is taken and the program ignores the account, so the config
+/// address stands in for it.
+pub fn fee_ata_b(svm: &LiteSVM, mint: &Pubkey) -> Pubkey {
+    let config = config_address().0;
+    if svm.get_account(&config).is_none() {
+        return config;
+    }
+    let fee_recipient = load_config(svm).fee_recipient;
+    token_account_address(&fee_recipient, mint, &token_program_of(svm, mint))
+}
+
+/// Has the maker, who deployed the program in `load_svm` and so holds its
+/// upgrade authority, create the config with itself as admin.
+pub fn create_config(s: &mut MakeSetup, variant: Variant) -> TransactionResult {
+    let ix = SetConfig {
+        authority: s.maker.pubkey(),
+        admin: s.maker.pubkey(),
+        fee_recipient: s.maker.pubkey(),
+        fee_basis_points: 0,
+    }
+    .instruction(variant);
+    send_as_maker(s, ix)
+}
+
+/// Has the maker, as config admin, charge `fee_basis_points` on every take,
+/// paid to a new fee recipient, creating the config first if needed. Returns
+/// the recipient's `mint_b` account.
+pub fn set_fee(s: &mut MakeSetup, variant: Variant, fee_basis_points: u16) -> Pubkey {
+    if s.svm.get_account(&config_address().0).is_none() {
+        create_config(s, variant).unwrap();
+    }
+
+    let fee_recipient = Pubkey::new_unique();
+    let fee_ata_b = if s.mint_b == NATIVE_MINT {
+        fee_recipient
+    } else {
+        CreateAssociatedTokenAccount::new(&mut s.svm, &s.maker, &s.mint_b)
+            .owner(&fee_recipient)
+            .send()
+            .unwrap()
     };
 
-    let msg = Message::new(&[ix], Some(&maker.pubkey()));
-    let blockhash = svm.latest_blockhash();
-    let tx = svm
-        .send_transaction(Transaction::new(&[&maker], msg, blockhash))
-        .unwrap();
+    let ix = SetConfig {
+        authority: s.maker.pubkey(),
+        admin: s.maker.pubkey(),
+        fee_recipient,
+        fee_basis_points,
+    }
+    .instruction(variant);
+    send_as_maker(s, ix).unwrap();
+    fee_ata_b
+}
 
-    MakeSetup {
-        svm,
-        maker,
-        mint_a,
-        mint_b,
-        escrow_pda,
-        escrow_ata,
-        maker_ata_a,
-        make_cu: tx.compute_units_consumed,
+pub fn send_take(s: &mut MakeSetup, t: &TakerSetup, variant: Variant) -> TransactionResult {
+    let ix = take(s, t).instruction(variant);
+    send_as_taker(s, t, ix)
+}
+
+pub fn send_take_many(
+    s: &mut MakeSetup,
+    t: &TakerSetup,
+    variant: Variant,
+    seeds: &[u64],
+) -> TransactionResult {
+    let ix = take_many(s, t, seeds).instruction(variant);
+    send_as_taker(s, t, ix)
+}
+
+pub fn send_take_partial(
+    s: &mut MakeSetup,
+    t: &TakerSetup,
+    variant: Variant,
+    fill: u64,
+) -> TransactionResult {
+    let ix = TakePartial {
+        take: take(s, t),
+        fill,
+    }
+    .instruction(variant);
+    send_as_taker(s, t, ix)
+}
+
+/// Sends `ix` paid and signed by the taker, so tests can alter a built
+/// instruction before sending it.
+pub fn send_as_taker(s: &mut MakeSetup, t: &TakerSetup, ix: Instruction) -> TransactionResult {
+    // Repeated fills send identical messages; a fresh blockhash keeps them
+    // from being rejected as already processed.
+    s.svm.expire_blockhash();
+    let msg = Message::new(&[ix], Some(&t.taker.pubkey()));
+    let blockhash = s.svm.latest_blockhash();
+    s.svm
+        .send_transaction(Transaction::new(&[&t.taker], msg, blockhash))
+}
+
+/// Remaining `(amount_to_receive, amount_to_give)` stored in an escrow account.
+pub fn escrow_amounts(svm: &LiteSVM, escrow: &Pubkey) -> (u64, u64) {
+    let escrow = load_escrow(svm, escrow);
+    (escrow.amount_to_receive, escrow.amount_to_give)
+}
+
+pub fn escrow_expires_at(svm: &LiteSVM, escrow: &Pubkey) -> i64 {
+    load_escrow(svm, escrow).expires_at
+}
+
+pub fn load_escrow(svm: &LiteSVM, escrow: &Pubkey) -> Escrow {
+    Escrow::decode(&svm.get_account(escrow).unwrap().data).unwrap()
+}
+
+pub fn warp_to(svm: &mut LiteSVM, unix_timestamp: i64) {
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = unix_timestamp;
+    svm.set_sysvar::<Clock>(&clock);
+}
+
+/// Sends `reclaim` for the escrow in `s`, paid and signed by `caller`.
+pub fn send_reclaim(s: &mut MakeSetup, caller: &Keypair, variant: Variant) -> TransactionResult {
+    let ix = Reclaim {
+        maker: s.maker.pubkey(),
+        seed: s.seed,
+        mint_a: s.mint_a,
+        maker_ata_a: s.maker_ata_a,
+        token_program: token_program_of(&s.svm, &s.mint_a),
+    }
+    .instruction(variant);
+
+    s.svm.expire_blockhash();
+    let msg = Message::new(&[ix], Some(&caller.pubkey()));
+    let blockhash = s.svm.latest_blockhash();
+    s.svm
+        .send_transaction(Transaction::new(&[caller], msg, blockhash))
+}
+
+/// `cancel` of the escrow in `s`.
+pub fn cancel(s: &MakeSetup) -> Cancel {
+    Cancel {
+        maker: s.maker.pubkey(),
+        seed: s.seed,
+        mint_a: s.mint_a,
+        maker_ata_a: s.maker_ata_a,
+        token_program: token_program_of(&s.svm, &s.mint_a),
     }
 }
+
+pub fn send_cancel(s: &mut MakeSetup, variant: Variant) -> TransactionResult {
+    let ix = cancel(s).instruction(variant);
+    send_as_maker(s, ix)
+}
+
+/// `amend` of the escrow in `s` to a new price and deposit.
+pub fn amend(s: &MakeSetup, amount_to_receive: u64, amount_to_give: u64) -> Amend {
+    Amend {
+        maker: s.maker.pubkey(),
+        seed: s.seed,
+        mint_a: s.mint_a,
+        maker_ata_a: s.maker_ata_a,
+        token_program: token_program_of(&s.svm, &s.mint_a),
+        amount_to_receive,
+        amount_to_give,
+    }
+}
+
+pub fn send_amend(
+    s: &mut MakeSetup,
+    variant: Variant,
+    amount_to_receive: u64,
+    amount_to_give: u64,
+) -> TransactionResult {
+    let ix = amend(s, amount_to_receive, amount_to_give).instruction(variant);
+    send_as_maker(s, ix)
+}
+
+/// Sends `ix` paid and signed by the maker.
+pub fn send_as_maker(s: &mut MakeSetup, ix: Instruction) -> TransactionResult {
+    s.svm.expire_blockhash();
+    let msg = Message::new(&[ix], Some(&s.maker.pubkey()));
+    let blockhash = s.svm.latest_blockhash();
+    s.svm
+        .send_transaction(Transaction::new(&[&s.maker], msg, blockhash))
+}
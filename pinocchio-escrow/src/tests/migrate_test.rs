@@ -0,0 +1,68 @@
+use escrow_client::{Migrate, Variant};
+use solana_signer::Signer;
+
+use super::helpers::{
+    load_escrow, send_as_taker, send_cancel, send_take, setup_make_with, setup_taker, token_amount,
+    MakeSetup,
+};
+
+const AMOUNT_TO_RECEIVE: u64 = 100_000_000;
+const AMOUNT_TO_GIVE: u64 = 500_000_000;
+
+/// Rewrites the escrow in `s` in the version 1 layout, which had no `taker`.
+fn downgrade(s: &mut MakeSetup) {
+    let mut account = s.svm.get_account(&s.escrow_pda).unwrap();
+    let mut data = account.data[..104].to_vec();
+    data.extend_from_slice(&account.data[136..]);
+    data[1] = 1;
+    account.data = data;
+    s.svm.set_account(s.escrow_pda, account).unwrap();
+}
+
+fn migrate(s: &MakeSetup, payer: &impl Signer) -> Migrate {
+    Migrate {
+        payer: payer.pubkey(),
+        maker: s.maker.pubkey(),
+        seed: s.seed,
+    }
+}
+
+#[test]
+fn test_migrate_then_cancel() {
+    for variant in Variant::ALL {
+        let mut s = setup_make_with(variant, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE);
+        let t = setup_taker(&mut s, 0);
+        let before = load_escrow(&s.svm, &s.escrow_pda);
+        downgrade(&mut s);
+        assert!(send_cancel(&mut s, variant).is_err());
+
+        // Anyone can migrate the offer; it comes back public and unchanged.
+        let ix = migrate(&s, &t.taker).instruction(variant);
+        send_as_taker(&mut s, &t, ix).unwrap();
+        assert_eq!(load_escrow(&s.svm, &s.escrow_pda), before);
+
+        // Migrating again finds the current layout and fails.
+        let ix = migrate(&s, &t.taker).instruction(variant);
+        assert!(send_as_taker(&mut s, &t, ix).is_err());
+
+        send_cancel(&mut s, variant).unwrap();
+        assert_eq!(token_amount(&s.svm, &s.maker_ata_a), AMOUNT_TO_GIVE);
+    }
+}
+
+#[test]
+fn test_migrate_then_take() {
+    for variant in Variant::ALL {
+        let mut s = setup_make_with(variant, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE);
+        let t = setup_taker(&mut s, AMOUNT_TO_RECEIVE);
+        downgrade(&mut s);
+        assert!(send_take(&mut s, &t, variant).is_err());
+
+        let ix = migrate(&s, &t.taker).instruction(variant);
+        send_as_taker(&mut s, &t, ix).unwrap();
+        send_take(&mut s, &t, variant).unwrap();
+
+        assert_eq!(token_amount(&s.svm, &t.taker_ata_a), AMOUNT_TO_GIVE);
+        assert_eq!(token_amount(&s.svm, &t.maker_ata_b), AMOUNT_TO_RECEIVE);
+    }
+}
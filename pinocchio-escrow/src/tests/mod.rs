@@ -9,3 +9,39 @@ mod wincode;
 
 #[cfg(test)]
 mod cu_table_test;
+
+#[cfg(test)]
+mod multi_escrow_test;
+
+#[cfg(test)]
+mod token_2022_test;
+
+#[cfg(test)]
+mod adversarial_test;
+
+#[cfg(test)]
+mod differential_test;
+
+#[cfg(test)]
+mod client_test;
+
+#[cfg(test)]
+mod fee_test;
+
+#[cfg(test)]
+mod designated_taker_test;
+
+#[cfg(test)]
+mod native_sol_test;
+
+#[cfg(test)]
+mod take_many_test;
+
+#[cfg(test)]
+mod cpi_test;
+
+#[cfg(test)]
+mod events_test;
+
+#[cfg(test)]
+mod migrate_test;
@@ -0,0 +1,89 @@
+use escrow_client::{escrow_address, vault_address, Cancel, Variant, TOKEN_PROGRAM_ID};
+use litesvm_token::MintTo;
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+
+use super::helpers::{
+    lamports, load_escrow, send_as_maker, send_make, setup_make, setup_make_v2, token_amount,
+    MakeSetup, Offer, DURATION,
+};
+
+const AMOUNT_TO_GIVE: u64 = 1_000_000;
+
+fn vault(s: &MakeSetup, seed: u64) -> (Pubkey, Pubkey) {
+    let (escrow_pda, _) = escrow_address(&s.maker.pubkey(), seed);
+    let escrow_ata = vault_address(&escrow_pda, &s.mint_a, &TOKEN_PROGRAM_ID);
+    (escrow_pda, escrow_ata)
+}
+
+fn make(s: &mut MakeSetup, variant: Variant, seed: u64, amount_to_receive: u64) -> bool {
+    let offer = Offer {
+        seed,
+        amount_to_receive,
+        amount_to_give: AMOUNT_TO_GIVE,
+        duration: DURATION,
+        taker: None,
+    };
+    send_make(
+        &mut s.svm,
+        &s.maker,
+        &s.mint_a,
+        &s.mint_b,
+        &s.maker_ata_a,
+        variant,
+        offer,
+    )
+    .is_ok()
+}
+
+fn cancel(s: &mut MakeSetup, variant: Variant, seed: u64) {
+    let ix = Cancel {
+        maker: s.maker.pubkey(),
+        seed,
+        mint_a: s.mint_a,
+        maker_ata_a: s.maker_ata_a,
+        token_program: TOKEN_PROGRAM_ID,
+    }
+    .instruction(variant);
+    send_as_maker(s, ix).unwrap();
+}
+
+fn check_concurrent_escrows(mut s: MakeSetup, variant: Variant) {
+    MintTo::new(
+        &mut s.svm,
+        &s.maker,
+        &s.mint_a,
+        &s.maker_ata_a,
+        3 * AMOUNT_TO_GIVE,
+    )
+    .send()
+    .unwrap();
+
+    // `setup` already opened seed 0; the same maker opens two more offers.
+    for seed in [1, 2] {
+        assert!(make(&mut s, variant, seed, 100 * seed));
+    }
+    // A seed that is still open cannot be reused.
+    assert!(!make(&mut s, variant, 1, 999));
+
+    for seed in [0, 1, 2] {
+        let (escrow_pda, escrow_ata) = vault(&s, seed);
+        assert_eq!(token_amount(&s.svm, &escrow_ata), AMOUNT_TO_GIVE);
+        assert_eq!(load_escrow(&s.svm, &escrow_pda).seed, seed);
+    }
+
+    cancel(&mut s, variant, 1);
+
+    assert_eq!(lamports(&s.svm, &vault(&s, 1).0), 0);
+    for seed in [0, 2] {
+        let (escrow_pda, escrow_ata) = vault(&s, seed);
+        assert!(lamports(&s.svm, &escrow_pda) > 0);
+        assert_eq!(token_amount(&s.svm, &escrow_ata), AMOUNT_TO_GIVE);
+    }
+}
+
+#[test]
+fn test_multiple_escrows_per_maker() {
+    check_concurrent_escrows(setup_make(100, AMOUNT_TO_GIVE), Variant::Unsafe);
+    check_concurrent_escrows(setup_make_v2(100, AMOUNT_TO_GIVE), Variant::Wincode);
+}
@@ -0,0 +1,301 @@
+use escrow_client::{
+    escrow_address, vault_address, Amend, Escrow, Variant, NATIVE_MINT, TOKEN_PROGRAM_ID,
+};
+use litesvm_token::{CreateAssociatedTokenAccount, CreateMint, MintTo};
+use solana_instruction::AccountMeta;
+use solana_keypair::Keypair;
+use solana_native_token::LAMPORTS_PER_SOL;
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+
+use super::helpers::{
+    escrow_expires_at, lamports, load_svm, send_as_maker, send_as_taker, send_make, send_reclaim,
+    send_take, send_take_partial, set_fee, take, token_amount, token_program_of, warp_to,
+    MakeSetup, Offer, TakerSetup, DURATION,
+};
+
+const AMOUNT_TO_RECEIVE: u64 = LAMPORTS_PER_SOL;
+const AMOUNT_TO_GIVE: u64 = 2 * LAMPORTS_PER_SOL;
+
+/// `(native_a, native_b)`: SOL offered for a token, a token for SOL, and SOL
+/// for SOL.
+const LEGS: [(bool, bool); 3] = [(true, false), (false, true), (true, true)];
+
+/// Opens an offer through `variant` with each leg either native SOL or a new
+/// SPL Token mint, and sets up a taker holding `AMOUNT_TO_RECEIVE` of mint_b.
+fn setup(variant: Variant, native_a: bool, native_b: bool) -> (MakeSetup, TakerSetup) {
+    let (mut svm, maker) = load_svm();
+
+    let mut mint = |native: bool| {
+        if native {
+            return NATIVE_MINT;
+        }
+        CreateMint::new(&mut svm, &maker)
+            .decimals(6)
+            .authority(&maker.pubkey())
+            .send()
+            .unwrap()
+    };
+    let mint_a = mint(native_a);
+    let mint_b = mint(native_b);
+
+    let maker_ata_a = if native_a {
+        maker.pubkey()
+    } else {
+        let maker_ata_a = CreateAssociatedTokenAccount::new(&mut svm, &maker, &mint_a)
+            .owner(&maker.pubkey())
+            .send()
+            .unwrap();
+        MintTo::new(&mut svm, &maker, &mint_a, &maker_ata_a, AMOUNT_TO_GIVE)
+            .send()
+            .unwrap();
+        maker_ata_a
+    };
+
+    let offer = Offer {
+        seed: 0,
+        amount_to_receive: AMOUNT_TO_RECEIVE,
+        amount_to_give: AMOUNT_TO_GIVE,
+        duration: DURATION,
+        taker: None,
+    };
+    let tx = send_make(
+        &mut svm,
+        &maker,
+        &mint_a,
+        &mint_b,
+        &maker_ata_a,
+        variant,
+        offer,
+    )
+    .unwrap();
+
+    let (escrow_pda, _) = escrow_address(&maker.pubkey(), offer.seed);
+    let escrow_ata = vault_address(&escrow_pda, &mint_a, &token_program_of(&svm, &mint_a));
+
+    let taker = Keypair::new();
+    svm.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    // SOL can be paid out to any wallet. A separate one keeps the balance
+    // checks clear of the taker's transaction fees.
+    let taker_ata_a = if native_a {
+        let receiver = Pubkey::new_unique();
+        svm.airdrop(&receiver, LAMPORTS_PER_SOL).unwrap();
+        receiver
+    } else {
+        CreateAssociatedTokenAccount::new(&mut svm, &taker, &mint_a)
+            .owner(&taker.pubkey())
+            .send()
+            .unwrap()
+    };
+
+    let (taker_ata_b, maker_ata_b) = if native_b {
+        (taker.pubkey(), maker.pubkey())
+    } else {
+        let taker_ata_b = CreateAssociatedTokenAccount::new(&mut svm, &taker, &mint_b)
+            .owner(&taker.pubkey())
+            .send()
+            .unwrap();
+        let maker_ata_b = CreateAssociatedTokenAccount::new(&mut svm, &taker, &mint_b)
+            .owner(&maker.pubkey())
+            .send()
+            .unwrap();
+        MintTo::new(&mut svm, &maker, &mint_b, &taker_ata_b, AMOUNT_TO_RECEIVE)
+            .send()
+            .unwrap();
+        (taker_ata_b, maker_ata_b)
+    };
+
+    let s = MakeSetup {
+        svm,
+        maker,
+        mint_a,
+        mint_b,
+        seed: offer.seed,
+        escrow_pda,
+        escrow_ata,
+        maker_ata_a,
+        make_cu: tx.compute_units_consumed,
+    };
+    let t = TakerSetup {
+        taker,
+        taker_ata_a,
+        taker_ata_b,
+        maker_ata_b,
+    };
+    (s, t)
+}
+
+/// Balance of the `mint` held in `account`: lamports for native SOL.
+fn balance(s: &MakeSetup, mint: &Pubkey, account: &Pubkey) -> u64 {
+    if *mint == NATIVE_MINT {
+        lamports(&s.svm, account)
+    } else {
+        token_amount(&s.svm, account)
+    }
+}
+
+#[test]
+fn test_native_take() {
+    for variant in Variant::ALL {
+        for (native_a, native_b) in LEGS {
+            let (mut s, t) = setup(variant, native_a, native_b);
+            let rent = s.svm.minimum_balance_for_rent_exemption(Escrow::LEN);
+
+            // A native deposit sits in the escrow account on top of its rent.
+            if native_a {
+                assert_eq!(s.escrow_ata, s.escrow_pda);
+                assert_eq!(lamports(&s.svm, &s.escrow_pda), rent + AMOUNT_TO_GIVE);
+            } else {
+                assert_eq!(token_amount(&s.svm, &s.escrow_ata), AMOUNT_TO_GIVE);
+            }
+
+            let maker = s.maker.pubkey();
+            let received_a = balance(&s, &s.mint_a, &t.taker_ata_a);
+            let vault_rent = if native_a {
+                0
+            } else {
+                lamports(&s.svm, &s.escrow_ata)
+            };
+            let maker_lamports = lamports(&s.svm, &maker);
+
+            send_take(&mut s, &t, variant).unwrap();
+
+            assert_eq!(
+                balance(&s, &s.mint_a, &t.taker_ata_a),
+                received_a + AMOUNT_TO_GIVE
+            );
+            // The maker gets the rent of both accounts back, plus the payment
+            // when it is in SOL.
+            let payment = if native_b {
+                AMOUNT_TO_RECEIVE
+            } else {
+                assert_eq!(token_amount(&s.svm, &t.maker_ata_b), AMOUNT_TO_RECEIVE);
+                0
+            };
+            assert_eq!(
+                lamports(&s.svm, &maker),
+                maker_lamports + rent + vault_rent + payment
+            );
+            assert_eq!(lamports(&s.svm, &s.escrow_pda), 0);
+        }
+    }
+}
+
+#[test]
+fn test_native_take_partial() {
+    for variant in Variant::ALL {
+        let (mut s, t) = setup(variant, true, true);
+        let rent = s.svm.minimum_balance_for_rent_exemption(Escrow::LEN);
+        let received = lamports(&s.svm, &t.taker_ata_a);
+
+        send_take_partial(&mut s, &t, variant, AMOUNT_TO_RECEIVE / 4).unwrap();
+        assert_eq!(
+            lamports(&s.svm, &t.taker_ata_a),
+            received + AMOUNT_TO_GIVE / 4
+        );
+        assert_eq!(
+            lamports(&s.svm, &s.escrow_pda),
+            rent + AMOUNT_TO_GIVE * 3 / 4
+        );
+
+        send_take_partial(&mut s, &t, variant, AMOUNT_TO_RECEIVE * 3 / 4).unwrap();
+        assert_eq!(lamports(&s.svm, &t.taker_ata_a), received + AMOUNT_TO_GIVE);
+        assert_eq!(lamports(&s.svm, &s.escrow_pda), 0);
+    }
+}
+
+#[test]
+fn test_native_fee() {
+    for variant in Variant::ALL {
+        let (mut s, t) = setup(variant, false, true);
+        let fee_recipient = set_fee(&mut s, variant, 100);
+        let fee = AMOUNT_TO_RECEIVE / 100;
+        let maker_lamports = lamports(&s.svm, &s.maker.pubkey());
+        let rent = lamports(&s.svm, &s.escrow_pda) + lamports(&s.svm, &s.escrow_ata);
+
+        send_take(&mut s, &t, variant).unwrap();
+
+        assert_eq!(lamports(&s.svm, &fee_recipient), fee);
+        assert_eq!(
+            lamports(&s.svm, &s.maker.pubkey()),
+            maker_lamports + rent + AMOUNT_TO_RECEIVE - fee
+        );
+    }
+}
+
+#[test]
+fn test_native_reclaim() {
+    for variant in Variant::ALL {
+        let (mut s, _) = setup(variant, true, false);
+        let caller = Keypair::new();
+        s.svm.airdrop(&caller.pubkey(), LAMPORTS_PER_SOL).unwrap();
+
+        warp_to(&mut s.svm, escrow_expires_at(&s.svm, &s.escrow_pda));
+        let maker_lamports = lamports(&s.svm, &s.maker.pubkey());
+        let escrow_lamports = lamports(&s.svm, &s.escrow_pda);
+        send_reclaim(&mut s, &caller, variant).unwrap();
+
+        // Deposit and rent both go back to the maker.
+        assert_eq!(
+            lamports(&s.svm, &s.maker.pubkey()),
+            maker_lamports + escrow_lamports
+        );
+        assert_eq!(lamports(&s.svm, &s.escrow_pda), 0);
+    }
+}
+
+#[test]
+fn test_native_amend() {
+    for variant in Variant::ALL {
+        let (mut s, _) = setup(variant, true, false);
+        let rent = s.svm.minimum_balance_for_rent_exemption(Escrow::LEN);
+        let (maker, seed) = (s.maker.pubkey(), s.seed);
+        let amend = move |amount_to_give| Amend {
+            maker,
+            seed,
+            mint_a: NATIVE_MINT,
+            maker_ata_a: maker,
+            token_program: NATIVE_MINT,
+            amount_to_receive: AMOUNT_TO_RECEIVE,
+            amount_to_give,
+        };
+
+        let ix = amend(AMOUNT_TO_GIVE * 2).instruction(variant);
+        send_as_maker(&mut s, ix).unwrap();
+        assert_eq!(lamports(&s.svm, &s.escrow_pda), rent + AMOUNT_TO_GIVE * 2);
+
+        let ix = amend(AMOUNT_TO_GIVE / 2).instruction(variant);
+        send_as_maker(&mut s, ix).unwrap();
+        assert_eq!(lamports(&s.svm, &s.escrow_pda), rent + AMOUNT_TO_GIVE / 2);
+    }
+}
+
+#[test]
+fn test_native_accounts_checked() {
+    for variant in Variant::ALL {
+        let (mut s, t) = setup(variant, true, true);
+
+        // The payment must go to the maker's own wallet.
+        let mut ix = take(&s, &t).instruction(variant);
+        ix.accounts[5] = AccountMeta::new(t.taker_ata_a, false);
+        assert!(send_as_taker(&mut s, &t, ix).is_err());
+
+        // The escrow itself is the vault.
+        let mut ix = take(&s, &t).instruction(variant);
+        ix.accounts[6] = AccountMeta::new(t.taker_ata_a, false);
+        assert!(send_as_taker(&mut s, &t, ix).is_err());
+
+        // A native leg goes through the system program, not a token program.
+        let mut ix = take(&s, &t).instruction(variant);
+        ix.accounts[10] = AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false);
+        assert!(send_as_taker(&mut s, &t, ix).is_err());
+
+        // The payout cannot be pointed at a program-owned account.
+        let mut ix = take(&s, &t).instruction(variant);
+        ix.accounts[3] = AccountMeta::new(s.escrow_pda, false);
+        assert!(send_as_taker(&mut s, &t, ix).is_err());
+
+        send_take(&mut s, &t, variant).unwrap();
+    }
+}
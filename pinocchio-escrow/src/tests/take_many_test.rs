@@ -0,0 +1,82 @@
+use escrow_client::{escrow_address, Cancel, Variant, TOKEN_PROGRAM_ID};
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+
+use super::helpers::{
+    escrow_amounts, lamports, send_as_maker, send_as_taker, send_take_many, setup_make_many,
+    setup_taker, take_many, token_amount, MakeSetup,
+};
+
+const AMOUNT_TO_RECEIVE: u64 = 100_000_000;
+const AMOUNT_TO_GIVE: u64 = 500_000_000;
+const COUNT: u64 = 3;
+
+fn escrow_pda(s: &MakeSetup, seed: u64) -> Pubkey {
+    escrow_address(&s.maker.pubkey(), seed).0
+}
+
+#[test]
+fn test_take_many() {
+    for variant in Variant::ALL {
+        let mut s = setup_make_many(variant, COUNT, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE);
+        let t = setup_taker(&mut s, COUNT * AMOUNT_TO_RECEIVE);
+
+        send_take_many(&mut s, &t, variant, &[0, 1, 2]).unwrap();
+
+        assert_eq!(token_amount(&s.svm, &t.taker_ata_a), COUNT * AMOUNT_TO_GIVE);
+        assert_eq!(
+            token_amount(&s.svm, &t.maker_ata_b),
+            COUNT * AMOUNT_TO_RECEIVE
+        );
+        for seed in 0..COUNT {
+            assert_eq!(lamports(&s.svm, &escrow_pda(&s, seed)), 0);
+        }
+    }
+}
+
+#[test]
+fn test_take_many_all_or_nothing() {
+    for variant in Variant::ALL {
+        let mut s = setup_make_many(variant, COUNT, AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE);
+        let t = setup_taker(&mut s, COUNT * AMOUNT_TO_RECEIVE);
+
+        let ix = Cancel {
+            maker: s.maker.pubkey(),
+            seed: 1,
+            mint_a: s.mint_a,
+            maker_ata_a: s.maker_ata_a,
+            token_program: TOKEN_PROGRAM_ID,
+        }
+        .instruction(variant);
+        send_as_maker(&mut s, ix).unwrap();
+
+        // The cancelled offer in the middle fails the batch, and the offer
+        // settled before it is rolled back.
+        assert!(send_take_many(&mut s, &t, variant, &[0, 1, 2]).is_err());
+        assert_eq!(
+            escrow_amounts(&s.svm, &escrow_pda(&s, 0)),
+            (AMOUNT_TO_RECEIVE, AMOUNT_TO_GIVE)
+        );
+        assert_eq!(token_amount(&s.svm, &t.taker_ata_a), 0);
+        assert_eq!(
+            token_amount(&s.svm, &t.taker_ata_b),
+            COUNT * AMOUNT_TO_RECEIVE
+        );
+
+        // The same offer twice.
+        assert!(send_take_many(&mut s, &t, variant, &[0, 0]).is_err());
+
+        // A count that does not match the accounts.
+        let mut ix = take_many(&s, &t, &[0, 2]).instruction(variant);
+        ix.data[1] = 3;
+        assert!(send_as_taker(&mut s, &t, ix).is_err());
+        let mut ix = take_many(&s, &t, &[0, 2]).instruction(variant);
+        ix.accounts.pop();
+        assert!(send_as_taker(&mut s, &t, ix).is_err());
+
+        assert!(send_take_many(&mut s, &t, variant, &[]).is_err());
+
+        send_take_many(&mut s, &t, variant, &[0, 2]).unwrap();
+        assert_eq!(token_amount(&s.svm, &t.taker_ata_a), 2 * AMOUNT_TO_GIVE);
+    }
+}
@@ -0,0 +1,282 @@
+use escrow_client::{escrow_address, vault_address, Variant, TOKEN_PROGRAM_ID};
+use litesvm::LiteSVM;
+use solana_instruction::{AccountMeta, Instruction};
+use solana_keypair::Keypair;
+use solana_message::Message;
+use solana_native_token::LAMPORTS_PER_SOL;
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::extension::{transfer_fee, ExtensionType};
+
+use super::helpers::{
+    cancel, escrow_amounts, lamports, load_svm, send_amend, send_as_maker, send_cancel, send_make,
+    send_take, token_amount, MakeSetup, Offer, TakerSetup, DURATION,
+};
+
+const AMOUNT_TO_RECEIVE: u64 = 100_000_000;
+const AMOUNT_TO_GIVE: u64 = 500_000_000;
+/// Transfer fees of the two mints, in basis points.
+const FEE_A: u16 = 100;
+const FEE_B: u16 = 200;
+
+fn send(svm: &mut LiteSVM, ixs: &[Instruction], signers: &[&Keypair]) {
+    let msg = Message::new(ixs, Some(&signers[0].pubkey()));
+    let blockhash = svm.latest_blockhash();
+    svm.send_transaction(Transaction::new(signers, msg, blockhash))
+        .unwrap();
+}
+
+/// Creates a Token-2022 mint with 6 decimals, with a transfer fee of
+/// `fee_basis_points` if one is given.
+fn create_mint(svm: &mut LiteSVM, authority: &Keypair, fee_basis_points: Option<u16>) -> Pubkey {
+    let mint = Keypair::new();
+    let extensions: &[ExtensionType] = match fee_basis_points {
+        Some(_) => &[ExtensionType::TransferFeeConfig],
+        None => &[],
+    };
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(extensions)
+        .unwrap();
+    let lamports = svm.minimum_balance_for_rent_exemption(space);
+
+    // System `CreateAccount`: [discriminator:4=0][lamports:8][space:8][owner:32]
+    let create_account = Instruction {
+        program_id: solana_sdk_ids::system_program::ID,
+        accounts: vec![
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new(mint.pubkey(), true),
+        ],
+        data: [
+            0u32.to_le_bytes().to_vec(),
+            lamports.to_le_bytes().to_vec(),
+            (space as u64).to_le_bytes().to_vec(),
+            spl_token_2022::ID.to_bytes().to_vec(),
+        ]
+        .concat(),
+    };
+
+    let mut ixs = vec![create_account];
+    if let Some(basis_points) = fee_basis_points {
+        ixs.push(
+            transfer_fee::instruction::initialize_transfer_fee_config(
+                &spl_token_2022::ID,
+                &mint.pubkey(),
+                None,
+                None,
+                basis_points,
+                u64::MAX,
+            )
+            .unwrap(),
+        );
+    }
+    ixs.push(
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::ID,
+            &mint.pubkey(),
+            &authority.pubkey(),
+            None,
+            6,
+        )
+        .unwrap(),
+    );
+
+    send(svm, &ixs, &[authority, &mint]);
+    mint.pubkey()
+}
+
+/// Creates `owner`'s Token-2022 associated token account for `mint`, minting
+/// `amount` into it.
+fn create_ata(
+    svm: &mut LiteSVM,
+    mint_authority: &Keypair,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let ata = get_associated_token_address_with_program_id(owner, mint, &spl_token_2022::ID);
+    let mut ixs = vec![
+        spl_associated_token_account::instruction::create_associated_token_account(
+            &mint_authority.pubkey(),
+            owner,
+            mint,
+            &spl_token_2022::ID,
+        ),
+    ];
+    if amount > 0 {
+        ixs.push(
+            spl_token_2022::instruction::mint_to(
+                &spl_token_2022::ID,
+                mint,
+                &ata,
+                &mint_authority.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap(),
+        );
+    }
+    send(svm, &ixs, &[mint_authority]);
+    ata
+}
+
+/// Opens an offer between two Token-2022 mints with transfer fees. The maker
+/// is minted twice the offer so the deposit fee is covered.
+fn setup(variant: Variant) -> MakeSetup {
+    let (mut svm, maker) = load_svm();
+
+    let mint_a = create_mint(&mut svm, &maker, Some(FEE_A));
+    let mint_b = create_mint(&mut svm, &maker, Some(FEE_B));
+    let maker_ata_a = create_ata(
+        &mut svm,
+        &maker,
+        &mint_a,
+        &maker.pubkey(),
+        2 * AMOUNT_TO_GIVE,
+    );
+
+    let offer = Offer {
+        seed: 0,
+        amount_to_receive: AMOUNT_TO_RECEIVE,
+        amount_to_give: AMOUNT_TO_GIVE,
+        duration: DURATION,
+        taker: None,
+    };
+    let tx = send_make(
+        &mut svm,
+        &maker,
+        &mint_a,
+        &mint_b,
+        &maker_ata_a,
+        variant,
+        offer,
+    )
+    .unwrap();
+
+    let (escrow_pda, _) = escrow_address(&maker.pubkey(), offer.seed);
+    let escrow_ata = vault_address(&escrow_pda, &mint_a, &spl_token_2022::ID);
+
+    MakeSetup {
+        svm,
+        maker,
+        mint_a,
+        mint_b,
+        seed: offer.seed,
+        escrow_pda,
+        escrow_ata,
+        maker_ata_a,
+        make_cu: tx.compute_units_consumed,
+    }
+}
+
+fn check_take_with_transfer_fees(variant: Variant) {
+    let mut s = setup(variant);
+
+    // The maker paid the deposit fee, so the vault holds the full offer.
+    assert_eq!(token_amount(&s.svm, &s.escrow_ata), AMOUNT_TO_GIVE);
+    assert!(token_amount(&s.svm, &s.maker_ata_a) < AMOUNT_TO_GIVE);
+
+    let taker = Keypair::new();
+    s.svm
+        .airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL)
+        .unwrap();
+    let mint_a = s.mint_a;
+    let mint_b = s.mint_b;
+    let maker = s.maker.pubkey();
+    let t = TakerSetup {
+        taker_ata_a: create_ata(&mut s.svm, &s.maker, &mint_a, &taker.pubkey(), 0),
+        taker_ata_b: create_ata(
+            &mut s.svm,
+            &s.maker,
+            &mint_b,
+            &taker.pubkey(),
+            2 * AMOUNT_TO_RECEIVE,
+        ),
+        maker_ata_b: create_ata(&mut s.svm, &s.maker, &mint_b, &maker, 0),
+        taker,
+    };
+
+    let tx = send_take(&mut s, &t, variant).unwrap();
+    println!("{:<12} | {:>6} CUs", "take 2022", tx.compute_units_consumed);
+
+    // The maker nets exactly what was asked; the taker covered the fee.
+    assert_eq!(token_amount(&s.svm, &t.maker_ata_b), AMOUNT_TO_RECEIVE);
+    let paid = 2 * AMOUNT_TO_RECEIVE - token_amount(&s.svm, &t.taker_ata_b);
+    assert!(paid > AMOUNT_TO_RECEIVE);
+    // The payout out of the vault is itself charged the mint_a fee.
+    assert_eq!(
+        token_amount(&s.svm, &t.taker_ata_a),
+        AMOUNT_TO_GIVE - AMOUNT_TO_GIVE * FEE_A as u64 / 10_000
+    );
+    assert_eq!(lamports(&s.svm, &s.escrow_pda), 0);
+    assert_eq!(lamports(&s.svm, &s.escrow_ata), 0);
+}
+
+#[test]
+fn test_take_with_transfer_fees() {
+    for variant in Variant::ALL {
+        check_take_with_transfer_fees(variant);
+    }
+}
+
+#[test]
+fn test_cancel_closes_vault_with_withheld_fees() {
+    for variant in Variant::ALL {
+        let mut s = setup(variant);
+        let before = token_amount(&s.svm, &s.maker_ata_a);
+
+        send_cancel(&mut s, variant).unwrap();
+
+        // The deposit fee withheld in the vault was harvested to the mint so
+        // the vault could be closed.
+        assert_eq!(
+            token_amount(&s.svm, &s.maker_ata_a) - before,
+            AMOUNT_TO_GIVE - AMOUNT_TO_GIVE * FEE_A as u64 / 10_000
+        );
+        assert_eq!(lamports(&s.svm, &s.escrow_pda), 0);
+        assert_eq!(lamports(&s.svm, &s.escrow_ata), 0);
+    }
+}
+
+#[test]
+fn test_amend_with_transfer_fees() {
+    for variant in Variant::ALL {
+        let mut s = setup(variant);
+
+        // The maker pays the fee on a top-up, so the vault grows by exactly
+        // the difference.
+        send_amend(
+            &mut s,
+            variant,
+            AMOUNT_TO_RECEIVE,
+            AMOUNT_TO_GIVE + 100_000_000,
+        )
+        .unwrap();
+        assert_eq!(
+            token_amount(&s.svm, &s.escrow_ata),
+            AMOUNT_TO_GIVE + 100_000_000
+        );
+
+        // A withdrawal is charged the fee on its way to the maker.
+        let before = token_amount(&s.svm, &s.maker_ata_a);
+        send_amend(&mut s, variant, AMOUNT_TO_RECEIVE, 100_000_000).unwrap();
+        assert_eq!(token_amount(&s.svm, &s.escrow_ata), 100_000_000);
+        assert_eq!(
+            token_amount(&s.svm, &s.maker_ata_a) - before,
+            AMOUNT_TO_GIVE - AMOUNT_TO_GIVE * FEE_A as u64 / 10_000
+        );
+        assert_eq!(
+            escrow_amounts(&s.svm, &s.escrow_pda),
+            (AMOUNT_TO_RECEIVE, 100_000_000)
+        );
+    }
+}
+
+#[test]
+fn test_wrong_token_program_rejected() {
+    let mut s = setup(Variant::Unsafe);
+    // The right vault, but the legacy program for a Token-2022 mint.
+    let mut ix = cancel(&s).instruction(Variant::Unsafe);
+    ix.accounts[4] = AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false);
+    assert!(send_as_maker(&mut s, ix).is_err());
+}
@@ -1,3 +1,6 @@
+mod amend;
 mod cancel;
 mod make;
+mod reclaim;
 mod take;
+mod take_partial;
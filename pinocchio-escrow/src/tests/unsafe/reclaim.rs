@@ -0,0 +1,50 @@
+use escrow_client::Variant;
+use solana_keypair::Keypair;
+use solana_native_token::LAMPORTS_PER_SOL;
+use solana_signer::Signer;
+
+use super::super::helpers::{
+    escrow_expires_at, lamports, send_reclaim, send_take, send_take_partial, setup_make,
+    setup_taker, token_amount, warp_to,
+};
+
+#[test]
+fn test_reclaim() {
+    let mut s = setup_make(100_000_000, 500_000_000);
+    let expires_at = escrow_expires_at(&s.svm, &s.escrow_pda);
+
+    // Anyone may reclaim, not just the maker.
+    let caller = Keypair::new();
+    s.svm.airdrop(&caller.pubkey(), LAMPORTS_PER_SOL).unwrap();
+
+    warp_to(&mut s.svm, expires_at - 1);
+    assert!(send_reclaim(&mut s, &caller, Variant::Unsafe).is_err());
+
+    warp_to(&mut s.svm, expires_at);
+    let maker_lamports = lamports(&s.svm, &s.maker.pubkey());
+    let rent = lamports(&s.svm, &s.escrow_pda) + lamports(&s.svm, &s.escrow_ata);
+    let tx = send_reclaim(&mut s, &caller, Variant::Unsafe).unwrap();
+    println!(
+        "{:<12} | {:>6} CUs",
+        "reclaim v1", tx.compute_units_consumed
+    );
+
+    assert_eq!(token_amount(&s.svm, &s.maker_ata_a), 500_000_000);
+    assert_eq!(lamports(&s.svm, &s.escrow_pda), 0);
+    assert_eq!(lamports(&s.svm, &s.escrow_ata), 0);
+    assert_eq!(lamports(&s.svm, &s.maker.pubkey()), maker_lamports + rent);
+}
+
+#[test]
+fn test_take_rejected_after_expiry() {
+    let mut s = setup_make(100_000_000, 500_000_000);
+    let expires_at = escrow_expires_at(&s.svm, &s.escrow_pda);
+    let t = setup_taker(&mut s, 100_000_000);
+
+    warp_to(&mut s.svm, expires_at - 1);
+    send_take_partial(&mut s, &t, Variant::Unsafe, 10_000_000).unwrap();
+
+    warp_to(&mut s.svm, expires_at);
+    assert!(send_take_partial(&mut s, &t, Variant::Unsafe, 10_000_000).is_err());
+    assert!(send_take(&mut s, &t, Variant::Unsafe).is_err());
+}
@@ -1,12 +1,12 @@
+use escrow_client::{Take, Variant, TOKEN_PROGRAM_ID};
 use litesvm_token::{CreateAssociatedTokenAccount, MintTo};
-use solana_instruction::{AccountMeta, Instruction};
 use solana_keypair::Keypair;
 use solana_message::Message;
 use solana_native_token::LAMPORTS_PER_SOL;
 use solana_signer::Signer;
 use solana_transaction::Transaction;
 
-use super::super::helpers::{program_id, setup_make, TOKEN_PROGRAM_ID};
+use super::super::helpers::{fee_ata_b, setup_make};
 
 #[test]
 fn test_take() {
@@ -45,20 +45,20 @@ fn test_take() {
     .send()
     .unwrap();
 
-    let ix = Instruction {
-        program_id: program_id(),
-        accounts: vec![
-            AccountMeta::new(taker.pubkey(), true),
-            AccountMeta::new(s.maker.pubkey(), false),
-            AccountMeta::new(s.escrow_pda, false),
-            AccountMeta::new(taker_ata_a, false),
-            AccountMeta::new(taker_ata_b, false),
-            AccountMeta::new(maker_ata_b, false),
-            AccountMeta::new(s.escrow_ata, false),
-            AccountMeta::new(TOKEN_PROGRAM_ID, false),
-        ],
-        data: vec![1u8],
-    };
+    let ix = Take {
+        taker: taker.pubkey(),
+        maker: s.maker.pubkey(),
+        seed: s.seed,
+        mint_a: s.mint_a,
+        mint_b: s.mint_b,
+        taker_ata_a,
+        taker_ata_b,
+        maker_ata_b,
+        token_program_a: TOKEN_PROGRAM_ID,
+        token_program_b: TOKEN_PROGRAM_ID,
+        fee_ata_b: fee_ata_b(&s.svm, &s.mint_b),
+    }
+    .instruction(Variant::Unsafe);
 
     let msg = Message::new(&[ix], Some(&taker.pubkey()));
     let blockhash = s.svm.latest_blockhash();
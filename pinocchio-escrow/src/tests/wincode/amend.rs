@@ -0,0 +1,68 @@
+use escrow_client::Variant;
+use litesvm_token::MintTo;
+use solana_instruction::AccountMeta;
+use solana_signer::Signer;
+
+use super::super::helpers::{
+    amend, escrow_amounts, escrow_expires_at, send_amend, send_as_taker, send_take, setup_make_v2,
+    setup_taker, token_amount, warp_to,
+};
+
+#[test]
+fn test_amend() {
+    let mut s = setup_make_v2(100_000_000, 500_000_000);
+    MintTo::new(&mut s.svm, &s.maker, &s.mint_a, &s.maker_ata_a, 200_000_000)
+        .send()
+        .unwrap();
+
+    // Raise the price and top up the deposit.
+    let tx = send_amend(&mut s, Variant::Wincode, 150_000_000, 700_000_000).unwrap();
+    println!("{:<12} | {:>6} CUs", "amend v2", tx.compute_units_consumed);
+    assert_eq!(
+        escrow_amounts(&s.svm, &s.escrow_pda),
+        (150_000_000, 700_000_000)
+    );
+    assert_eq!(token_amount(&s.svm, &s.escrow_ata), 700_000_000);
+    assert_eq!(token_amount(&s.svm, &s.maker_ata_a), 0);
+
+    // Withdraw most of it again.
+    send_amend(&mut s, Variant::Wincode, 150_000_000, 200_000_000).unwrap();
+    assert_eq!(
+        escrow_amounts(&s.svm, &s.escrow_pda),
+        (150_000_000, 200_000_000)
+    );
+    assert_eq!(token_amount(&s.svm, &s.escrow_ata), 200_000_000);
+    assert_eq!(token_amount(&s.svm, &s.maker_ata_a), 500_000_000);
+
+    // The offer is taken at the amended terms.
+    let t = setup_taker(&mut s, 150_000_000);
+    send_take(&mut s, &t, Variant::Wincode).unwrap();
+    assert_eq!(token_amount(&s.svm, &t.taker_ata_a), 200_000_000);
+    assert_eq!(token_amount(&s.svm, &t.maker_ata_b), 150_000_000);
+}
+
+#[test]
+fn test_amend_rejected() {
+    let mut s = setup_make_v2(100_000_000, 500_000_000);
+    let t = setup_taker(&mut s, 0);
+
+    // Only the maker may amend.
+    let mut ix = amend(&s, 1, 1).instruction(Variant::Wincode);
+    ix.accounts[0] = AccountMeta::new(t.taker.pubkey(), true);
+    assert!(send_as_taker(&mut s, &t, ix).is_err());
+
+    // Emptying either side is a cancel, and the top-up has to be covered.
+    assert!(send_amend(&mut s, Variant::Wincode, 0, 500_000_000).is_err());
+    assert!(send_amend(&mut s, Variant::Wincode, 100_000_000, 0).is_err());
+    assert!(send_amend(&mut s, Variant::Wincode, 100_000_000, 500_000_001).is_err());
+
+    let expires_at = escrow_expires_at(&s.svm, &s.escrow_pda);
+    warp_to(&mut s.svm, expires_at);
+    assert!(send_amend(&mut s, Variant::Wincode, 100_000_000, 400_000_000).is_err());
+
+    assert_eq!(
+        escrow_amounts(&s.svm, &s.escrow_pda),
+        (100_000_000, 500_000_000)
+    );
+    assert_eq!(token_amount(&s.svm, &s.escrow_ata), 500_000_000);
+}
@@ -1,11 +1,10 @@
+use escrow_client::{Make, Variant, TOKEN_PROGRAM_ID};
 use litesvm_token::{CreateAssociatedTokenAccount, CreateMint, MintTo};
-use solana_instruction::{AccountMeta, Instruction};
 use solana_message::Message;
-use solana_pubkey::Pubkey;
 use solana_signer::Signer;
 use solana_transaction::Transaction;
 
-use super::super::helpers::{load_svm, program_id, ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID};
+use super::super::helpers::{load_svm, DURATION};
 
 #[test]
 fn test_make() {
@@ -30,43 +29,25 @@ fn test_make() {
 
     let amount_to_receive = 100_000_000u64;
     let amount_to_give = 500_000_000u64;
-
-    let (escrow_pda, bump) =
-        Pubkey::find_program_address(&[b"escrow", maker.pubkey().as_ref()], &program_id());
-
-    let escrow_ata =
-        spl_associated_token_account::get_associated_token_address(&escrow_pda, &mint_a);
+    let seed = 42u64;
 
     MintTo::new(&mut svm, &maker, &mint_a, &maker_ata_a, amount_to_give)
         .send()
         .unwrap();
 
-    let data = [
-        vec![3u8],
-        amount_to_receive.to_le_bytes().to_vec(),
-        amount_to_give.to_le_bytes().to_vec(),
-        vec![bump],
-    ]
-    .concat();
-
-    let ix = Instruction {
-        program_id: program_id(),
-        accounts: vec![
-            AccountMeta::new(maker.pubkey(), true),
-            AccountMeta::new(mint_a, false),
-            AccountMeta::new(mint_b, false),
-            AccountMeta::new(escrow_pda, false),
-            AccountMeta::new(maker_ata_a, false),
-            AccountMeta::new(escrow_ata, false),
-            AccountMeta::new(solana_sdk_ids::system_program::ID, false),
-            AccountMeta::new(TOKEN_PROGRAM_ID, false),
-            AccountMeta::new(
-                ASSOCIATED_TOKEN_PROGRAM_ID.parse::<Pubkey>().unwrap(),
-                false,
-            ),
-        ],
-        data,
-    };
+    let ix = Make {
+        maker: maker.pubkey(),
+        mint_a,
+        mint_b,
+        maker_ata_a,
+        token_program: TOKEN_PROGRAM_ID,
+        seed,
+        amount_to_receive,
+        amount_to_give,
+        duration: DURATION,
+        taker: None,
+    }
+    .instruction(Variant::Wincode);
 
     let msg = Message::new(&[ix], Some(&maker.pubkey()));
     let blockhash = svm.latest_blockhash();
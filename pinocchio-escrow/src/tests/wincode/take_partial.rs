@@ -0,0 +1,46 @@
+use escrow_client::Variant;
+
+use super::super::helpers::{
+    escrow_amounts, lamports, send_take_partial, setup_make_v2, setup_taker, token_amount,
+};
+
+#[test]
+fn test_take_partial() {
+    let mut s = setup_make_v2(300, 1_000);
+    let t = setup_taker(&mut s, 300);
+
+    // Each fill releases floor(fill * amount_to_give / amount_to_receive) of
+    // what is left, and the final fill releases the whole remainder.
+    for (fill, released, remaining) in [
+        (100, 333, (200, 667)),
+        (100, 333, (100, 334)),
+        (100, 334, (0, 0)),
+    ] {
+        let before = token_amount(&s.svm, &t.taker_ata_a);
+        let tx = send_take_partial(&mut s, &t, Variant::Wincode, fill).unwrap();
+        println!(
+            "{:<12} | {:>6} CUs",
+            "partial v2", tx.compute_units_consumed
+        );
+
+        assert_eq!(token_amount(&s.svm, &t.taker_ata_a) - before, released);
+        if remaining.0 > 0 {
+            assert_eq!(escrow_amounts(&s.svm, &s.escrow_pda), remaining);
+            assert_eq!(token_amount(&s.svm, &s.escrow_ata), remaining.1);
+        }
+    }
+
+    assert_eq!(token_amount(&s.svm, &t.maker_ata_b), 300);
+    assert_eq!(lamports(&s.svm, &s.escrow_pda), 0);
+}
+
+#[test]
+fn test_take_partial_rejects_overfill() {
+    let mut s = setup_make_v2(300, 1_000);
+    let t = setup_taker(&mut s, 400);
+
+    send_take_partial(&mut s, &t, Variant::Wincode, 250).unwrap();
+    assert!(send_take_partial(&mut s, &t, Variant::Wincode, 51).is_err());
+    assert!(send_take_partial(&mut s, &t, Variant::Wincode, 0).is_err());
+    assert_eq!(escrow_amounts(&s.svm, &s.escrow_pda), (50, 167));
+}
@@ -0,0 +1,344 @@
+//! Token program helpers that work with both SPL Token and Token-2022.
+//!
+//! `pinocchio_token` hardcodes the SPL Token program id and rejects token
+//! accounts that carry extensions, so the escrow builds its own CPIs and reads
+//! the fields it needs straight from the base account layout, which Token-2022
+//! keeps identical and only appends to.
+//!
+//! Either leg of an offer can also be native SOL, marked by [`NATIVE_MINT`] in
+//! place of the mint. Such a leg has no token accounts: wallets stand in for
+//! their own, the escrow account itself is the vault, and the system program
+//! is passed as both the mint and the token program.
+
+use core::mem::MaybeUninit;
+use core::slice::from_raw_parts;
+
+use pinocchio::{
+    cpi::{invoke_signed_unchecked, CpiAccount, Signer},
+    error::ProgramError,
+    instruction::{InstructionAccount, InstructionView},
+    AccountView, Address, ProgramResult,
+};
+use pinocchio_system::instructions::Transfer;
+
+use crate::entrypoint::ID;
+
+pub const TOKEN_2022_ID: Address =
+    Address::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Mint of a leg paid in lamports: the system program's id. Not the wrapped
+/// SOL mint; native legs never touch token accounts.
+pub const NATIVE_MINT: Address = Address::new_from_array([0; 32]);
+/// Lamports have 9 decimals, like wrapped SOL.
+const NATIVE_DECIMALS: u8 = 9;
+
+const UNINIT_BYTE: MaybeUninit<u8> = MaybeUninit::<u8>::uninit();
+
+/// Size of a base token account; Token-2022 puts an account type byte and the
+/// extensions after it.
+const TOKEN_ACCOUNT_LEN: usize = 165;
+/// Size of a base mint. Token-2022 pads extended mints to `TOKEN_ACCOUNT_LEN`
+/// so the account type byte sits at the same offset for both kinds.
+const MINT_LEN: usize = 82;
+/// First TLV entry of an extended Token-2022 account, after the account type.
+const EXTENSIONS_OFFSET: usize = TOKEN_ACCOUNT_LEN + 1;
+
+const EXTENSION_UNINITIALIZED: u16 = 0;
+const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+const MAX_FEE_BASIS_POINTS: u64 = 10_000;
+
+/// Whether `mint` stands for native SOL rather than a token.
+pub fn is_native(mint: &AccountView) -> bool {
+    mint.address() == &NATIVE_MINT
+}
+
+/// Fails unless `token_program` is SPL Token or Token-2022 and owns `mint`, or,
+/// for a native leg, is the system program.
+pub fn check_token_program(token_program: &AccountView, mint: &AccountView) -> ProgramResult {
+    let program_id = token_program.address();
+    if is_native(mint) {
+        if program_id != &NATIVE_MINT {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        return Ok(());
+    }
+    if program_id != &pinocchio_token::ID && program_id != &TOKEN_2022_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !mint.owned_by(program_id) {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Fails unless `account` is an initialized token account of either program
+/// for `mint`, and, when `owner` is given, owned by it. Extensions are allowed.
+///
+/// For a native leg `account` must be `owner` itself, or any system account
+/// when no owner is required.
+pub fn check_token_account(
+    account: &AccountView,
+    mint: &AccountView,
+    owner: Option<&Address>,
+) -> ProgramResult {
+    if is_native(mint) {
+        let valid = match owner {
+            Some(owner) => account.address() == owner,
+            None => account.owned_by(&NATIVE_MINT),
+        };
+        if !valid {
+            return Err(ProgramError::IllegalOwner);
+        }
+        return Ok(());
+    }
+
+    if !account.owned_by(&pinocchio_token::ID) && !account.owned_by(&TOKEN_2022_ID) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let data = unsafe { account.borrow_unchecked() };
+    // Byte 108 is the account state; 0 is uninitialized.
+    if data.len() < TOKEN_ACCOUNT_LEN || data[108] == 0 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if let Some(owner) = owner {
+        if &data[32..64] != owner.as_array() {
+            return Err(ProgramError::IllegalOwner);
+        }
+    }
+    if &data[0..32] != mint.address().as_array() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+/// Decimals of an initialized mint, needed by `TransferChecked`.
+pub fn mint_decimals(mint: &AccountView) -> Result<u8, ProgramError> {
+    if is_native(mint) {
+        return Ok(NATIVE_DECIMALS);
+    }
+    let data = unsafe { mint.borrow_unchecked() };
+    // Byte 44 is `decimals`, byte 45 `is_initialized`.
+    if data.len() < MINT_LEN || data[45] != 1 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(data[44])
+}
+
+/// Amount to send so the recipient is credited `amount` after the mint's
+/// transfer fee for `epoch`. Mints without the transfer fee extension take no
+/// fee, so this is `amount` itself.
+///
+/// Mirrors `TransferFee::calculate_pre_fee_amount` in Token-2022: the fee is
+/// rounded up on the gross amount, so the inverse has to round up as well.
+pub fn amount_with_fee(mint: &AccountView, amount: u64, epoch: u64) -> Result<u64, ProgramError> {
+    let Some((maximum_fee, basis_points)) = transfer_fee(mint, epoch)? else {
+        return Ok(amount);
+    };
+
+    match (basis_points, amount) {
+        (0, _) | (_, 0) => Ok(amount),
+        (MAX_FEE_BASIS_POINTS, _) => amount
+            .checked_add(maximum_fee)
+            .ok_or(ProgramError::ArithmeticOverflow),
+        _ => {
+            let numerator = amount as u128 * MAX_FEE_BASIS_POINTS as u128;
+            let denominator = (MAX_FEE_BASIS_POINTS - basis_points) as u128;
+            let gross = numerator.div_ceil(denominator);
+            if gross - amount as u128 >= maximum_fee as u128 {
+                amount
+                    .checked_add(maximum_fee)
+                    .ok_or(ProgramError::ArithmeticOverflow)
+            } else {
+                u64::try_from(gross).map_err(|_| ProgramError::ArithmeticOverflow)
+            }
+        }
+    }
+}
+
+/// `(maximum_fee, basis_points)` of the transfer fee in effect for `epoch`, or
+/// `None` when the mint has no transfer fee extension.
+fn transfer_fee(mint: &AccountView, epoch: u64) -> Result<Option<(u64, u64)>, ProgramError> {
+    let data = unsafe { mint.borrow_unchecked() };
+    let Some(config) = find_extension(mint, data, EXTENSION_TRANSFER_FEE_CONFIG)? else {
+        return Ok(None);
+    };
+
+    // config authority (32) | withdraw authority (32) | withheld amount (8) |
+    // older fee (18) | newer fee (18), each fee being epoch (8) |
+    // maximum fee (8) | basis points (2).
+    if config.len() < 108 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let newer_epoch = u64::from_le_bytes(config[90..98].try_into().unwrap());
+    let fee = if epoch >= newer_epoch {
+        &config[90..108]
+    } else {
+        &config[72..90]
+    };
+    Ok(Some((
+        u64::from_le_bytes(fee[8..16].try_into().unwrap()),
+        u16::from_le_bytes([fee[16], fee[17]]) as u64,
+    )))
+}
+
+/// Value of the `kind` extension in a Token-2022 account's TLV data.
+fn find_extension<'a>(
+    account: &AccountView,
+    data: &'a [u8],
+    kind: u16,
+) -> Result<Option<&'a [u8]>, ProgramError> {
+    if !account.owned_by(&TOKEN_2022_ID) {
+        return Ok(None);
+    }
+
+    let mut offset = EXTENSIONS_OFFSET;
+    while offset + 4 <= data.len() {
+        let entry = u16::from_le_bytes([data[offset], data[offset + 1]]);
+        let len = u16::from_le_bytes([data[offset + 2], data[offset + 3]]) as usize;
+        let value = data
+            .get(offset + 4..offset + 4 + len)
+            .ok_or(ProgramError::InvalidAccountData)?;
+
+        if entry == EXTENSION_UNINITIALIZED {
+            break;
+        }
+        if entry == kind {
+            return Ok(Some(value));
+        }
+        offset += 4 + len;
+    }
+    Ok(None)
+}
+
+/// `TransferChecked` through `token_program`, which must own `mint`.
+///
+/// A native leg moves lamports instead. A wallet pays through the system
+/// program, and the escrow, which this program owns, is debited directly.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_checked(
+    token_program: &AccountView,
+    from: &AccountView,
+    mint: &AccountView,
+    to: &AccountView,
+    authority: &AccountView,
+    amount: u64,
+    decimals: u8,
+    signers: &[Signer],
+) -> ProgramResult {
+    if is_native(mint) {
+        if !from.owned_by(&ID) {
+            return Transfer {
+                from,
+                to,
+                lamports: amount,
+            }
+            .invoke_signed(signers);
+        }
+
+        let from_lamports = from
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        from.set_lamports(from_lamports);
+        // Read after the debit, in case `to` is `from`.
+        let to_lamports = to
+            .lamports()
+            .checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        to.set_lamports(to_lamports);
+        return Ok(());
+    }
+
+    let instruction_accounts = [
+        InstructionAccount::writable(from.address()),
+        InstructionAccount::readonly(mint.address()),
+        InstructionAccount::writable(to.address()),
+        InstructionAccount::readonly_signer(authority.address()),
+    ];
+
+    // [discriminator:1=12][amount:8][decimals:1]
+    let mut data = [UNINIT_BYTE; 10];
+    unsafe {
+        (data.as_mut_ptr() as *mut u8).write(12u8);
+        ((data.as_mut_ptr() as *mut u8).add(1) as *mut u64).write_unaligned(amount);
+        (data.as_mut_ptr() as *mut u8).add(9).write(decimals);
+    }
+
+    let instruction = InstructionView {
+        program_id: token_program.address(),
+        accounts: &instruction_accounts,
+        data: unsafe { from_raw_parts(data.as_ptr() as *const u8, 10) },
+    };
+
+    let cpi_accounts = [
+        CpiAccount::from(from),
+        CpiAccount::from(mint),
+        CpiAccount::from(to),
+        CpiAccount::from(authority),
+    ];
+
+    unsafe { invoke_signed_unchecked(&instruction, &cpi_accounts, signers) }
+
+    Ok(())
+}
+
+/// Closes the escrow's token account, first moving any transfer fees withheld
+/// in it back to the mint; Token-2022 refuses to close an account that still
+/// holds withheld fees.
+///
+/// A native vault is the escrow account, which the caller closes itself.
+pub fn close_vault(
+    token_program: &AccountView,
+    vault: &AccountView,
+    mint: &AccountView,
+    destination: &AccountView,
+    authority: &AccountView,
+    signers: &[Signer],
+) -> ProgramResult {
+    if is_native(mint) {
+        return Ok(());
+    }
+
+    let mint_data = unsafe { mint.borrow_unchecked() };
+    if find_extension(mint, mint_data, EXTENSION_TRANSFER_FEE_CONFIG)?.is_some() {
+        let instruction_accounts = [
+            InstructionAccount::writable(mint.address()),
+            InstructionAccount::writable(vault.address()),
+        ];
+
+        // [TransferFeeExtension:1=26][HarvestWithheldTokensToMint:1=4]
+        let instruction = InstructionView {
+            program_id: token_program.address(),
+            accounts: &instruction_accounts,
+            data: &[26, 4],
+        };
+
+        let cpi_accounts = [CpiAccount::from(mint), CpiAccount::from(vault)];
+
+        unsafe { invoke_signed_unchecked(&instruction, &cpi_accounts, &[]) }
+    }
+
+    let instruction_accounts = [
+        InstructionAccount::writable(vault.address()),
+        InstructionAccount::writable(destination.address()),
+        InstructionAccount::readonly_signer(authority.address()),
+    ];
+
+    let instruction = InstructionView {
+        program_id: token_program.address(),
+        accounts: &instruction_accounts,
+        data: &[9],
+    };
+
+    let cpi_accounts = [
+        CpiAccount::from(vault),
+        CpiAccount::from(destination),
+        CpiAccount::from(authority),
+    ];
+
+    unsafe { invoke_signed_unchecked(&instruction, &cpi_accounts, signers) }
+
+    Ok(())
+}
@@ -2,6 +2,7 @@ use pinocchio::{
     ProgramResult,
     cpi::{Seed, Signer},
     entrypoint::InstructionContext,
+    error::ProgramError,
 };
 
 use crate::{raw_cpi, states::Fundraiser};
@@ -16,6 +17,10 @@ pub fn process_checker(ctx: &mut InstructionContext) -> ProgramResult {
     let fundraiser_data = unsafe { fundraiser.borrow_unchecked() };
     let state = unsafe { &*(fundraiser_data.as_ptr() as *const Fundraiser) };
 
+    if !state.goal_reached() {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
     let bump = state.bump;
     let current_amount = state.current_amount;
 
@@ -1,6 +1,8 @@
 use pinocchio::{
     ProgramResult,
     entrypoint::InstructionContext,
+    error::ProgramError,
+    sysvars::{Sysvar, clock::Clock},
 };
 
 use crate::{raw_cpi, states::{Contributor, Fundraiser}};
@@ -19,12 +21,16 @@ pub fn process_contribute(ctx: &mut InstructionContext) -> ProgramResult {
         *(ix_data.as_ptr().add(1) as *const [u8; 8])
     });
 
+    let fs = unsafe { &mut *(fundraiser.borrow_unchecked_mut().as_mut_ptr() as *mut Fundraiser) };
+    if Clock::get()?.unix_timestamp >= fs.deadline() {
+        return Err(ProgramError::InvalidArgument);
+    }
+
     raw_cpi::raw_transfer_signed(&contributor_ata, &vault, &contributor, amount, &[])?;
 
     let cs = unsafe { &mut *(contributor_state.borrow_unchecked_mut().as_mut_ptr() as *mut Contributor) };
     cs.amount = cs.amount.wrapping_add(amount);
 
-    let fs = unsafe { &mut *(fundraiser.borrow_unchecked_mut().as_mut_ptr() as *mut Fundraiser) };
     fs.current_amount = fs.current_amount.wrapping_add(amount);
 
     Ok(())
@@ -2,7 +2,7 @@ use pinocchio::{
     ProgramResult,
     cpi::{Seed, Signer},
     entrypoint::InstructionContext,
-    sysvars::rent::ACCOUNT_STORAGE_OVERHEAD,
+    sysvars::{Sysvar, clock::Clock, rent::ACCOUNT_STORAGE_OVERHEAD},
 };
 
 use crate::{
@@ -52,7 +52,7 @@ pub fn process_initialize(ctx: &mut InstructionContext) -> ProgramResult {
     fs.mint_to_raise = *mint.address().as_array();
     fs.amount_to_raise = amount_to_raise;
     fs.current_amount = 0;
-    fs.time_started = 0;
+    fs.time_started = Clock::get()?.unix_timestamp;
     fs.duration = duration;
     fs.bump = bump;
 
@@ -2,9 +2,16 @@ use pinocchio::{
     ProgramResult,
     cpi::{Seed, Signer},
     entrypoint::InstructionContext,
+    error::ProgramError,
+    sysvars::{Sysvar, clock::Clock},
 };
+use pinocchio_pubkey::derive_address;
 
-use crate::{raw_cpi, states::{Contributor, Fundraiser}};
+use crate::{
+    entrypoint::{ID, PROGRAM_ADDRESS},
+    raw_cpi,
+    states::{Contributor, Fundraiser},
+};
 
 #[inline(always)]
 pub fn process_refund(ctx: &mut InstructionContext) -> ProgramResult {
@@ -14,8 +21,52 @@ pub fn process_refund(ctx: &mut InstructionContext) -> ProgramResult {
     let contributor_ata = unsafe { ctx.next_account_unchecked() }.assume_account();
     let contributor_state = unsafe { ctx.next_account_unchecked() }.assume_account();
 
-    let fundraiser_data = unsafe { fundraiser.borrow_unchecked() };
-    let fs = unsafe { &*(fundraiser_data.as_ptr() as *const Fundraiser) };
+    if !contributor.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !fundraiser.owned_by(&PROGRAM_ADDRESS) || !contributor_state.owned_by(&PROGRAM_ADDRESS) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let ix_data = unsafe { ctx.instruction_data_unchecked() };
+    if ix_data.len() < 9 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let refund_amount = u64::from_le_bytes(unsafe {
+        *(ix_data.as_ptr().add(1) as *const [u8; 8])
+    });
+    if refund_amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let fs = Fundraiser::load_mut(unsafe { fundraiser.borrow_unchecked_mut() })?;
+
+    // Only a fundraiser that closed short of its goal gives money back.
+    if Clock::get()?.unix_timestamp < fs.deadline() || fs.goal_reached() {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let cs = Contributor::load_mut(unsafe { contributor_state.borrow_unchecked_mut() })?;
+
+    // The state must be the contributor's own for this fundraiser, or anyone
+    // could claim another contributor's deposit.
+    if cs.contributor != *contributor.address().as_array() {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+    let seeds: [&[u8]; 4] = [
+        b"contributor",
+        fundraiser.address().as_array(),
+        contributor.address().as_array(),
+        &[cs.bump],
+    ];
+    if derive_address(&seeds, None, &ID) != *contributor_state.address().as_array() {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if refund_amount > cs.amount {
+        return Err(ProgramError::InsufficientFunds);
+    }
 
     let maker = fs.maker;
     let bump_arr = [fs.bump];
@@ -25,10 +76,6 @@ pub fn process_refund(ctx: &mut InstructionContext) -> ProgramResult {
         Seed::from(&bump_arr),
     ];
 
-    let contributor_data = unsafe { contributor_state.borrow_unchecked() };
-    let cs = unsafe { &*(contributor_data.as_ptr() as *const Contributor) };
-    let refund_amount = cs.amount;
-
     raw_cpi::raw_transfer_signed(
         &vault,
         &contributor_ata,
@@ -37,8 +84,20 @@ pub fn process_refund(ctx: &mut InstructionContext) -> ProgramResult {
         &[Signer::from(&signer_seeds[..])],
     )?;
 
-    contributor.set_lamports(contributor.lamports() + contributor_state.lamports());
-    contributor_state.set_lamports(0);
+    cs.amount = cs
+        .amount
+        .checked_sub(refund_amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    fs.current_amount = fs
+        .current_amount
+        .checked_sub(refund_amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // Keep the contributor state open until everything has been refunded.
+    if cs.amount == 0 {
+        contributor.set_lamports(contributor.lamports() + contributor_state.lamports());
+        contributor_state.set_lamports(0);
+    }
 
     Ok(())
 }
@@ -15,3 +15,19 @@ pub struct Fundraiser {
 
 impl_len!(Fundraiser);
 impl_load!(Fundraiser);
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+impl Fundraiser {
+    /// Unix timestamp from which contributions are closed, `duration` days
+    /// after `time_started`.
+    #[inline(always)]
+    pub fn deadline(&self) -> i64 {
+        self.time_started + self.duration as i64 * SECONDS_PER_DAY
+    }
+
+    #[inline(always)]
+    pub fn goal_reached(&self) -> bool {
+        self.current_amount >= self.amount_to_raise
+    }
+}
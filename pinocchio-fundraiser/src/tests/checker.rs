@@ -4,7 +4,10 @@ use solana_message::Message;
 use solana_signer::Signer;
 use solana_transaction::Transaction;
 
-use super::helpers::{TOKEN_PROGRAM_ID, program_id, setup_contribute};
+use super::helpers::{
+    TOKEN_PROGRAM_ID, deadline, lamports, load_fundraiser, program_id, send_checker,
+    setup_contribute, token_amount, warp_to,
+};
 
 #[test]
 fn test_checker() {
@@ -37,3 +40,45 @@ fn test_checker() {
 
     println!("{:<20} | {:>6} CUs", "checker", tx.compute_units_consumed);
 }
+
+#[test]
+fn test_checker_requires_goal() {
+    let goal = 1_000_000_000u64;
+    let mut s = setup_contribute(goal, 7, goal / 2);
+
+    let maker_ata = CreateAssociatedTokenAccount::new(&mut s.svm, &s.maker, &s.mint)
+        .owner(&s.maker.pubkey())
+        .send()
+        .unwrap();
+
+    assert!(send_checker(&mut s, &maker_ata).is_err());
+
+    // Running out of time does not lower the goal.
+    warp_to(&mut s.svm, deadline(7));
+    assert!(send_checker(&mut s, &maker_ata).is_err());
+
+    assert_eq!(token_amount(&s.svm, &maker_ata), 0);
+    assert_eq!(token_amount(&s.svm, &s.vault), goal / 2);
+}
+
+#[test]
+fn test_checker_after_deadline() {
+    let goal = 1_000_000_000u64;
+    let mut s = setup_contribute(goal, 1, goal);
+
+    let maker_ata = CreateAssociatedTokenAccount::new(&mut s.svm, &s.maker, &s.mint)
+        .owner(&s.maker.pubkey())
+        .send()
+        .unwrap();
+
+    // A goal met in time can still be claimed once contributions close.
+    warp_to(&mut s.svm, deadline(1) + 1);
+    assert_eq!(
+        load_fundraiser(&s.svm, &s.fundraiser_pda).current_amount,
+        goal
+    );
+    send_checker(&mut s, &maker_ata).unwrap();
+
+    assert_eq!(token_amount(&s.svm, &maker_ata), goal);
+    assert_eq!(lamports(&s.svm, &s.fundraiser_pda), 0);
+}
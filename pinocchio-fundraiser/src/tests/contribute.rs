@@ -1,7 +1,27 @@
-use super::helpers::setup_contribute;
+use super::helpers::{
+    deadline, load_fundraiser, send_contribute, setup_contribute, setup_create_contributor, warp_to,
+};
 
 #[test]
 fn test_contribute() {
     let s = setup_contribute(1_000_000_000, 7, 500_000_000);
     println!("{:<20} | {:>6} CUs", "contribute", s.contribute_cu);
 }
+
+#[test]
+fn test_contribute_rejected_after_deadline() {
+    let amount = 500_000_000u64;
+    let mut s = setup_create_contributor(1_000_000_000, 1, amount);
+
+    warp_to(&mut s.svm, deadline(1));
+    assert!(send_contribute(&mut s, amount).is_err());
+    assert_eq!(load_fundraiser(&s.svm, &s.fundraiser_pda).current_amount, 0);
+
+    // The last second before the deadline is still open.
+    warp_to(&mut s.svm, deadline(1) - 1);
+    send_contribute(&mut s, amount).unwrap();
+    assert_eq!(
+        load_fundraiser(&s.svm, &s.fundraiser_pda).current_amount,
+        amount
+    );
+}
@@ -1,7 +1,8 @@
 use std::path::PathBuf;
 
-use litesvm::LiteSVM;
+use litesvm::{LiteSVM, types::TransactionResult};
 use litesvm_token::{CreateAssociatedTokenAccount, CreateMint, MintTo, spl_token};
+use solana_clock::Clock;
 use solana_instruction::{AccountMeta, Instruction};
 use solana_keypair::Keypair;
 use solana_message::Message;
@@ -10,8 +11,13 @@ use solana_pubkey::Pubkey;
 use solana_signer::Signer;
 use solana_transaction::Transaction;
 
+use crate::states::{Contributor, Fundraiser, SECONDS_PER_DAY};
+
 pub const TOKEN_PROGRAM_ID: Pubkey = spl_token::ID;
 
+/// Clock time at which `setup_initialize` starts the fundraiser.
+pub const START: i64 = 1_700_000_000;
+
 pub fn program_id() -> Pubkey {
     Pubkey::from(crate::ID)
 }
@@ -40,6 +46,40 @@ pub fn fundraiser_pda(maker: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[b"fundraiser", maker.as_ref()], &program_id())
 }
 
+pub fn warp_to(svm: &mut LiteSVM, unix_timestamp: i64) {
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = unix_timestamp;
+    svm.set_sysvar::<Clock>(&clock);
+}
+
+/// First second at which a fundraiser started at `START` for `duration` days
+/// is closed.
+pub fn deadline(duration: u8) -> i64 {
+    START + duration as i64 * SECONDS_PER_DAY
+}
+
+pub fn load_fundraiser(svm: &LiteSVM, fundraiser: &Pubkey) -> Fundraiser {
+    let data = svm.get_account(fundraiser).unwrap().data;
+    assert_eq!(data.len(), Fundraiser::LEN);
+    unsafe { core::ptr::read_unaligned(data.as_ptr() as *const Fundraiser) }
+}
+
+pub fn load_contributor(svm: &LiteSVM, contributor_state: &Pubkey) -> Contributor {
+    let data = svm.get_account(contributor_state).unwrap().data;
+    assert_eq!(data.len(), Contributor::LEN);
+    unsafe { core::ptr::read_unaligned(data.as_ptr() as *const Contributor) }
+}
+
+pub fn token_amount(svm: &LiteSVM, address: &Pubkey) -> u64 {
+    let account = svm.get_account(address).unwrap();
+    u64::from_le_bytes(account.data[64..72].try_into().unwrap())
+}
+
+pub fn lamports(svm: &LiteSVM, address: &Pubkey) -> u64 {
+    svm.get_account(address)
+        .map_or(0, |account| account.lamports)
+}
+
 pub fn contributor_pda(fundraiser: &Pubkey, contributor: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(
         &[b"contributor", fundraiser.as_ref(), contributor.as_ref()],
@@ -58,6 +98,7 @@ pub struct InitializeSetup {
 
 pub fn setup_initialize(amount_to_raise: u64, duration: u8) -> InitializeSetup {
     let (mut svm, maker) = load_svm();
+    warp_to(&mut svm, START);
 
     let mint = CreateMint::new(&mut svm, &maker)
         .decimals(6)
@@ -200,10 +241,26 @@ pub fn setup_contribute(
     contribute_amount: u64,
 ) -> ContributeSetup {
     let mut s = setup_create_contributor(amount_to_raise, duration, contribute_amount);
+    let tx = send_contribute(&mut s, contribute_amount).unwrap();
 
+    ContributeSetup {
+        svm: s.svm,
+        maker: s.maker,
+        contributor: s.contributor,
+        mint: s.mint,
+        fundraiser_pda: s.fundraiser_pda,
+        vault: s.vault,
+        contributor_ata: s.contributor_ata,
+        contributor_state_pda: s.contributor_state_pda,
+        contribute_cu: tx.compute_units_consumed,
+    }
+}
+
+/// Sends `contribute` of `amount` from the contributor in `s`.
+pub fn send_contribute(s: &mut CreateContributorSetup, amount: u64) -> TransactionResult {
     // discriminator 2 = contribute; data[1..9] = amount
     let mut data = vec![2u8];
-    data.extend_from_slice(&contribute_amount.to_le_bytes());
+    data.extend_from_slice(&amount.to_le_bytes());
 
     let ix = Instruction {
         program_id: program_id(),
@@ -218,22 +275,58 @@ pub fn setup_contribute(
         data,
     };
 
+    // A fresh blockhash lets a test repeat an identical contribution.
+    s.svm.expire_blockhash();
     let msg = Message::new(&[ix], Some(&s.contributor.pubkey()));
     let blockhash = s.svm.latest_blockhash();
-    let tx = s
-        .svm
+    s.svm
         .send_transaction(Transaction::new(&[&s.contributor], msg, blockhash))
-        .unwrap();
+}
 
-    ContributeSetup {
-        svm: s.svm,
-        maker: s.maker,
-        contributor: s.contributor,
-        mint: s.mint,
-        fundraiser_pda: s.fundraiser_pda,
-        vault: s.vault,
-        contributor_ata: s.contributor_ata,
-        contributor_state_pda: s.contributor_state_pda,
-        contribute_cu: tx.compute_units_consumed,
-    }
+/// Sends `checker` for the fundraiser in `s`, paying the vault out to
+/// `maker_ata`.
+pub fn send_checker(s: &mut ContributeSetup, maker_ata: &Pubkey) -> TransactionResult {
+    let ix = Instruction {
+        program_id: program_id(),
+        accounts: vec![
+            AccountMeta::new(s.maker.pubkey(), true),
+            AccountMeta::new(s.fundraiser_pda, false),
+            AccountMeta::new(s.vault, false),
+            AccountMeta::new(*maker_ata, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        data: vec![3u8], // discriminator 3 = checker
+    };
+
+    s.svm.expire_blockhash();
+    let msg = Message::new(&[ix], Some(&s.maker.pubkey()));
+    let blockhash = s.svm.latest_blockhash();
+    s.svm
+        .send_transaction(Transaction::new(&[&s.maker], msg, blockhash))
+}
+
+/// Sends `refund` of `amount` to the contributor in `s`.
+pub fn send_refund(s: &mut ContributeSetup, amount: u64) -> TransactionResult {
+    // discriminator 4 = refund; data[1..9] = amount
+    let mut data = vec![4u8];
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    let ix = Instruction {
+        program_id: program_id(),
+        accounts: vec![
+            AccountMeta::new(s.contributor.pubkey(), true),
+            AccountMeta::new(s.fundraiser_pda, false),
+            AccountMeta::new(s.vault, false),
+            AccountMeta::new(s.contributor_ata, false),
+            AccountMeta::new(s.contributor_state_pda, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        data,
+    };
+
+    s.svm.expire_blockhash();
+    let msg = Message::new(&[ix], Some(&s.contributor.pubkey()));
+    let blockhash = s.svm.latest_blockhash();
+    s.svm
+        .send_transaction(Transaction::new(&[&s.contributor], msg, blockhash))
 }
@@ -1,7 +1,17 @@
-use super::helpers::setup_initialize;
+use super::helpers::{START, deadline, load_fundraiser, setup_initialize};
 
 #[test]
 fn test_initialize() {
     let s = setup_initialize(1_000_000_000, 7);
     println!("{:<12} | {:>6} CUs", "initialize", s.init_cu);
 }
+
+#[test]
+fn test_initialize_records_start() {
+    let s = setup_initialize(1_000_000_000, 7);
+
+    let fundraiser = load_fundraiser(&s.svm, &s.fundraiser_pda);
+    assert_eq!(fundraiser.time_started, START);
+    assert_eq!(fundraiser.duration, 7);
+    assert_eq!(fundraiser.deadline(), deadline(7));
+}
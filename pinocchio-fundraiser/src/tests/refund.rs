@@ -1,10 +1,13 @@
-use solana_clock::Clock;
-use solana_instruction::{AccountMeta, Instruction};
-use solana_message::Message;
+use litesvm_token::CreateAssociatedTokenAccount;
+use solana_keypair::Keypair;
+use solana_native_token::LAMPORTS_PER_SOL;
+use solana_pubkey::Pubkey;
 use solana_signer::Signer;
-use solana_transaction::Transaction;
 
-use super::helpers::{TOKEN_PROGRAM_ID, program_id, setup_contribute};
+use super::helpers::{
+    deadline, lamports, load_contributor, load_fundraiser, send_refund, setup_contribute,
+    token_amount, warp_to,
+};
 
 #[test]
 fn test_refund() {
@@ -13,30 +16,112 @@ fn test_refund() {
 
     let mut s = setup_contribute(goal, 1, contributed);
 
-    // warp clock past the 1-day deadline (time_started=0, deadline=86400)
-    let mut clock = s.svm.get_sysvar::<Clock>();
-    clock.unix_timestamp = 86401;
-    s.svm.set_sysvar::<Clock>(&clock);
-
-    let ix = Instruction {
-        program_id: program_id(),
-        accounts: vec![
-            AccountMeta::new(s.contributor.pubkey(), true),
-            AccountMeta::new(s.fundraiser_pda, false),
-            AccountMeta::new(s.vault, false),
-            AccountMeta::new(s.contributor_ata, false),
-            AccountMeta::new(s.contributor_state_pda, false),
-            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
-        ],
-        data: vec![4u8], // discriminator 4 = refund
-    };
-
-    let msg = Message::new(&[ix], Some(&s.contributor.pubkey()));
-    let blockhash = s.svm.latest_blockhash();
-    let tx = s
-        .svm
-        .send_transaction(Transaction::new(&[&s.contributor], msg, blockhash))
-        .unwrap();
+    // warp clock past the 1-day deadline
+    warp_to(&mut s.svm, deadline(1) + 1);
+
+    let tx = send_refund(&mut s, contributed).unwrap();
+
+    assert_eq!(token_amount(&s.svm, &s.contributor_ata), contributed);
+    assert_eq!(token_amount(&s.svm, &s.vault), 0);
+    assert_eq!(lamports(&s.svm, &s.contributor_state_pda), 0);
 
     println!("{:<20} | {:>6} CUs", "refund", tx.compute_units_consumed);
 }
+
+#[test]
+fn test_refund_before_deadline() {
+    let contributed = 500_000_000u64;
+    let mut s = setup_contribute(1_000_000_000, 1, contributed);
+
+    assert!(send_refund(&mut s, contributed).is_err());
+
+    // The deadline itself is the first second refunds open.
+    warp_to(&mut s.svm, deadline(1) - 1);
+    assert!(send_refund(&mut s, contributed).is_err());
+    warp_to(&mut s.svm, deadline(1));
+    send_refund(&mut s, contributed).unwrap();
+}
+
+#[test]
+fn test_refund_after_goal_reached() {
+    let goal = 1_000_000_000u64;
+    let mut s = setup_contribute(goal, 1, goal);
+
+    warp_to(&mut s.svm, deadline(1) + 1);
+    assert!(send_refund(&mut s, goal).is_err());
+    assert!(send_refund(&mut s, 1).is_err());
+
+    assert_eq!(token_amount(&s.svm, &s.vault), goal);
+    assert_eq!(
+        load_contributor(&s.svm, &s.contributor_state_pda).amount,
+        goal
+    );
+}
+
+#[test]
+fn test_refund_partial() {
+    let goal = 1_000_000_000u64;
+    let contributed = 500_000_000u64;
+    let first = 200_000_000u64;
+
+    let mut s = setup_contribute(goal, 1, contributed);
+    warp_to(&mut s.svm, deadline(1) + 1);
+
+    // Neither nothing nor more than was contributed can be refunded.
+    assert!(send_refund(&mut s, 0).is_err());
+    assert!(send_refund(&mut s, contributed + 1).is_err());
+
+    send_refund(&mut s, first).unwrap();
+    assert_eq!(token_amount(&s.svm, &s.contributor_ata), first);
+    assert_eq!(
+        load_contributor(&s.svm, &s.contributor_state_pda).amount,
+        contributed - first
+    );
+    assert_eq!(
+        load_fundraiser(&s.svm, &s.fundraiser_pda).current_amount,
+        contributed - first
+    );
+    assert_ne!(lamports(&s.svm, &s.contributor_state_pda), 0);
+
+    assert!(send_refund(&mut s, contributed - first + 1).is_err());
+
+    // The last of it closes the contributor state.
+    send_refund(&mut s, contributed - first).unwrap();
+    assert_eq!(token_amount(&s.svm, &s.contributor_ata), contributed);
+    assert_eq!(load_fundraiser(&s.svm, &s.fundraiser_pda).current_amount, 0);
+    assert_eq!(lamports(&s.svm, &s.contributor_state_pda), 0);
+}
+
+#[test]
+fn test_refund_someone_elses_state() {
+    let contributed = 500_000_000u64;
+    let mut s = setup_contribute(1_000_000_000, 1, contributed);
+    warp_to(&mut s.svm, deadline(1) + 1);
+
+    // Another signer passes the contributor's state with their own account.
+    let thief = Keypair::new();
+    s.svm
+        .airdrop(&thief.pubkey(), 10 * LAMPORTS_PER_SOL)
+        .unwrap();
+    let thief_ata = CreateAssociatedTokenAccount::new(&mut s.svm, &thief, &s.mint)
+        .owner(&thief.pubkey())
+        .send()
+        .unwrap();
+    let contributor = std::mem::replace(&mut s.contributor, thief);
+    let contributor_ata = std::mem::replace(&mut s.contributor_ata, thief_ata);
+    assert!(send_refund(&mut s, contributed).is_err());
+    assert_eq!(token_amount(&s.svm, &thief_ata), 0);
+    s.contributor = contributor;
+    s.contributor_ata = contributor_ata;
+
+    // A copy of the state owned by another program is not accepted either.
+    let genuine = s.svm.get_account(&s.contributor_state_pda).unwrap();
+    let mut foreign = genuine.clone();
+    foreign.owner = Pubkey::new_unique();
+    s.svm.set_account(s.contributor_state_pda, foreign).unwrap();
+    assert!(send_refund(&mut s, contributed).is_err());
+    s.svm.set_account(s.contributor_state_pda, genuine).unwrap();
+
+    send_refund(&mut s, contributed).unwrap();
+    assert_eq!(token_amount(&s.svm, &s.contributor_ata), contributed);
+}